@@ -46,6 +46,8 @@ export class CronService {
                 console.error(`[cron] Task "${name}" failed:`, err);
             }
         }, entry.task.intervalMs);
+        // Background work alone should never keep the process alive
+        entry.timer.unref();
     }
 
     stop(name: string): void {
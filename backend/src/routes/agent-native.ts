@@ -1,5 +1,5 @@
 import { Router, Request, Response } from 'express';
-import { SessionStore } from '../session/session-store';
+import { SessionStore, sessionTtlMs } from '../session/session-store';
 
 /**
  * Agent-Native API Routes
@@ -180,6 +180,7 @@ export function createAgentNativeRoutes(sessionStore: SessionStore): Router {
         const sessions = sessionStore.listSessions();
         res.json({
             count: sessions.length,
+            ttlMs: sessionTtlMs(),
             sessions: sessions.map((s) => ({
                 sessionId: s.id,
                 isPaid: s.isPaid,
@@ -0,0 +1,55 @@
+import request from 'supertest';
+import { createApp } from '../server';
+
+process.env.NODE_ENV = 'test';
+
+// A file of its own: confirm-payment is rate limited per IP and routes.test.ts uses up that budget
+const app = createApp();
+
+describe('POST /api/chat/confirm-payment concurrency', () => {
+    it('should settle a tx for one session when two confirms race', async () => {
+        const { NODE_ENV } = process.env;
+        process.env.NODE_ENV = 'development';
+        const chatA = await request(app).post('/api/chat').send({ message: 'Hello' });
+        const chatB = await request(app).post('/api/chat').send({ message: 'Hello' });
+        const { token } = chatA.body.payment;
+
+        // Hold both verifications on the chain until each confirm is in flight
+        let release!: () => void;
+        const gate = new Promise<void>((resolve) => { release = resolve; });
+        const fetchSpy = jest.spyOn(global, 'fetch').mockImplementation(async () => {
+            await gate;
+            return {
+                status: 200,
+                ok: true,
+                json: async () => ({
+                    status: 'success',
+                    value: '1000000000000000000000',
+                    action: {
+                        arguments: {
+                            transfers: [{ token, value: '1000000000000000000000', decimals: 18 }],
+                        },
+                    },
+                }),
+            } as unknown as Response;
+        });
+        try {
+            const txHash = 'fedcba0987654321fedcba0987654321fedcba0987654321fedcba0987654321';
+            const confirm = (sessionId: string) => request(app)
+                .post('/api/chat/confirm-payment')
+                .send({ sessionId, txHash })
+                .then((res) => res);
+            const pending = Promise.all([confirm(chatA.body.sessionId), confirm(chatB.body.sessionId)]);
+            while (fetchSpy.mock.calls.length < 2) {
+                await new Promise((resolve) => setTimeout(resolve, 5));
+            }
+            release();
+
+            const statuses = (await pending).map((res) => res.status).sort((a, b) => a - b);
+            expect(statuses).toEqual([200, 409]);
+        } finally {
+            fetchSpy.mockRestore();
+            process.env.NODE_ENV = NODE_ENV;
+        }
+    });
+});
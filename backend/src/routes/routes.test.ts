@@ -1,5 +1,13 @@
 import request from 'supertest';
-import { createApp, sanitizeFilename } from '../server';
+import {
+    createApp,
+    paymentTokenDecimals,
+    sanitizeFilename,
+    startSessionCleanup,
+    toAtomicUnits,
+    verifyTransactionOnChain,
+} from '../server';
+import { SessionStore } from '../session/session-store';
 import path from 'path';
 import fs from 'fs';
 
@@ -32,6 +40,27 @@ describe('API Routes', () => {
             expect(res.body.name).toBeDefined();
             expect(res.body.pricing).toBeDefined();
         });
+
+        it('should advertise the chain id payments are signed for', async () => {
+            const res = await request(app).get('/api/agent');
+            expect(typeof res.body.chainId).toBe('string');
+            expect(res.body.chainId.length).toBeGreaterThan(0);
+        });
+
+        it('should advertise the agent wallet address', async () => {
+            const previous = process.env.AGENT_WALLET_ADDRESS;
+            process.env.AGENT_WALLET_ADDRESS = 'erd1qyu5wthldzr8wx5c9ucg8kjagg0jfs53s8nr3zpz3hypefsdd8ssycr6th';
+            try {
+                const res = await request(app).get('/api/agent');
+                expect(res.body.address).toBe(process.env.AGENT_WALLET_ADDRESS);
+            } finally {
+                if (previous === undefined) {
+                    delete process.env.AGENT_WALLET_ADDRESS;
+                } else {
+                    process.env.AGENT_WALLET_ADDRESS = previous;
+                }
+            }
+        });
     });
 
     describe('POST /api/chat', () => {
@@ -104,6 +133,33 @@ describe('API Routes', () => {
         });
     });
 
+    describe('POST /api/chat/quote', () => {
+        it('should return the same terms as the 402 without creating a session', async () => {
+            const before = await request(app).get('/api/sessions');
+            const quote = await request(app)
+                .post('/api/chat/quote')
+                .send({ message: 'Research AI trends' });
+            const after = await request(app).get('/api/sessions');
+
+            expect(quote.status).toBe(200);
+            expect(quote.body.sessionId).toBeUndefined();
+            expect(after.body.count).toBe(before.body.count);
+
+            const gate = await request(app)
+                .post('/api/chat')
+                .send({ message: 'Research AI trends' });
+            expect(gate.status).toBe(402);
+            expect(quote.body.payment).toEqual(gate.body.payment);
+        });
+
+        it('should return 400 if no message provided', async () => {
+            const res = await request(app)
+                .post('/api/chat/quote')
+                .send({});
+            expect(res.status).toBe(400);
+        });
+    });
+
     describe('POST /api/chat/confirm-payment', () => {
         it('should return 400 if sessionId or txHash missing', async () => {
             const res = await request(app)
@@ -213,3 +269,204 @@ describe('sanitizeFilename', () => {
         expect(sanitizeFilename('my-report.pdf')).toBe('my-report.pdf');
     });
 });
+
+describe('startSessionCleanup', () => {
+    it('should run the cleanup task until stopped', () => {
+        const cron = startSessionCleanup(new SessionStore());
+        expect(cron.isRunning('session-cleanup')).toBe(true);
+        cron.stopAll();
+        expect(cron.isRunning('session-cleanup')).toBe(false);
+    });
+});
+
+describe('verifyTransactionOnChain', () => {
+    it('should report chain_unavailable when the API is unreachable', async () => {
+        const { NODE_ENV, MULTIVERSX_API_URL } = process.env;
+        process.env.NODE_ENV = 'development';
+        process.env.MULTIVERSX_API_URL = 'http://127.0.0.1:1';
+        try {
+            const verification = await verifyTransactionOnChain('abcdef1234567890');
+            expect(verification).toEqual({ valid: false, status: 'chain_unavailable' });
+        } finally {
+            process.env.NODE_ENV = NODE_ENV;
+            if (MULTIVERSX_API_URL === undefined) {
+                delete process.env.MULTIVERSX_API_URL;
+            } else {
+                process.env.MULTIVERSX_API_URL = MULTIVERSX_API_URL;
+            }
+        }
+    });
+
+    it('should only report chain_unavailable for gateway errors', async () => {
+        const { NODE_ENV } = process.env;
+        process.env.NODE_ENV = 'development';
+        const fetchSpy = jest.spyOn(global, 'fetch')
+            .mockResolvedValueOnce({ status: 503, ok: false } as Response)
+            .mockResolvedValueOnce({ status: 400, ok: false } as Response);
+        try {
+            expect(await verifyTransactionOnChain('abcdef1234567890'))
+                .toEqual({ valid: false, status: 'chain_unavailable' });
+            expect(await verifyTransactionOnChain('abcdef1234567890'))
+                .toEqual({ valid: false, status: 'invalid' });
+        } finally {
+            fetchSpy.mockRestore();
+            process.env.NODE_ENV = NODE_ENV;
+        }
+    });
+
+    it('should report a reverted tx with the contract error', async () => {
+        const { NODE_ENV } = process.env;
+        process.env.NODE_ENV = 'development';
+        const fetchSpy = jest.spyOn(global, 'fetch').mockResolvedValueOnce({
+            status: 200,
+            ok: true,
+            json: async () => ({
+                status: 'fail',
+                receiver: 'erd1agent',
+                value: '500000',
+                logs: {
+                    events: [{
+                        identifier: 'signalError',
+                        topics: ['', Buffer.from('function does not accept EGLD payment').toString('base64')],
+                    }],
+                },
+            }),
+        } as unknown as Response);
+        try {
+            const verification = await verifyTransactionOnChain('abcdef1234567890', 'erd1agent');
+            expect(verification).toEqual({
+                valid: false,
+                status: 'reverted',
+                reason: 'function does not accept EGLD payment',
+            });
+        } finally {
+            fetchSpy.mockRestore();
+            process.env.NODE_ENV = NODE_ENV;
+        }
+    });
+
+    describe('receiver of an ESDT payment', () => {
+        const agent = 'erd1agent';
+        const payer = 'erd1payer';
+        const transfers = [{ token: 'USDC-350c4e', value: '500000', decimals: 6 }];
+        const verify = async (tx: Record<string, unknown>) => {
+            const { NODE_ENV } = process.env;
+            process.env.NODE_ENV = 'development';
+            const fetchSpy = jest.spyOn(global, 'fetch').mockResolvedValueOnce({
+                status: 200,
+                ok: true,
+                json: async () => ({ status: 'success', value: '0', ...tx }),
+            } as unknown as Response);
+            try {
+                return await verifyTransactionOnChain(
+                    'abcdef1234567890',
+                    agent,
+                    { amount: '0.50', token: 'USDC-350c4e', decimals: 6 },
+                );
+            } finally {
+                fetchSpy.mockRestore();
+                process.env.NODE_ENV = NODE_ENV;
+            }
+        };
+
+        it('should accept an ESDTTransfer sent straight to the agent', async () => {
+            const verification = await verify({
+                receiver: agent,
+                function: 'ESDTTransfer',
+                action: { category: 'esdtNft', name: 'transfer', arguments: { transfers, receiver: agent } },
+            });
+            expect(verification).toEqual({ valid: true, status: 'success' });
+        });
+
+        it('should accept a MultiESDTNFTTransfer whose action names the agent', async () => {
+            const verification = await verify({
+                receiver: payer,
+                function: 'MultiESDTNFTTransfer',
+                action: { category: 'esdtNft', name: 'transfer', arguments: { transfers, receiver: agent } },
+            });
+            expect(verification).toEqual({ valid: true, status: 'success' });
+        });
+
+        it('should reject a MultiESDTNFTTransfer to someone else', async () => {
+            const verification = await verify({
+                receiver: payer,
+                function: 'MultiESDTNFTTransfer',
+                action: { category: 'esdtNft', name: 'transfer', arguments: { transfers, receiver: 'erd1other' } },
+            });
+            expect(verification).toEqual({ valid: false, status: 'wrong_receiver' });
+        });
+    });
+
+    describe('amount of an ESDT payment', () => {
+        const verify = async (value: string, decimals?: number) => {
+            const { NODE_ENV } = process.env;
+            process.env.NODE_ENV = 'development';
+            // The API leaves out the token's decimals, so only the configured ones can scale the price
+            const fetchSpy = jest.spyOn(global, 'fetch').mockResolvedValueOnce({
+                status: 200,
+                ok: true,
+                json: async () => ({
+                    status: 'success',
+                    receiver: 'erd1agent',
+                    value: '0',
+                    action: { arguments: { transfers: [{ token: 'USDC-350c4e', value }], receiver: 'erd1agent' } },
+                }),
+            } as unknown as Response);
+            try {
+                return await verifyTransactionOnChain(
+                    'abcdef1234567890',
+                    'erd1agent',
+                    { amount: '2', token: 'USDC-350c4e', decimals },
+                );
+            } finally {
+                fetchSpy.mockRestore();
+                process.env.NODE_ENV = NODE_ENV;
+            }
+        };
+
+        it('should accept the full price in the smallest unit', async () => {
+            expect(await verify('2000000', 6)).toEqual({ valid: true, status: 'success' });
+        });
+
+        it('should report insufficient_amount for less than the price', async () => {
+            expect(await verify('1999999', 6)).toEqual({ valid: false, status: 'insufficient_amount' });
+        });
+
+        it('should fail closed when the token decimals are unknown', async () => {
+            expect(await verify('2000000')).toEqual({ valid: false, status: 'unknown_decimals' });
+        });
+    });
+});
+
+describe('paymentTokenDecimals', () => {
+    it('should prefer the configured decimals over known tokens', () => {
+        expect(paymentTokenDecimals('USDC-350c4e', 2)).toBe(2);
+        expect(paymentTokenDecimals('USDC-350c4e')).toBe(6);
+        expect(paymentTokenDecimals('EGLD')).toBe(18);
+    });
+
+    it('should leave an unknown token without decimals', () => {
+        const { PRICE_TOKEN_DECIMALS } = process.env;
+        delete process.env.PRICE_TOKEN_DECIMALS;
+        try {
+            expect(paymentTokenDecimals('WEGLD-bd4d79')).toBeUndefined();
+            expect(paymentTokenDecimals('WEGLD-bd4d79', 'many')).toBeUndefined();
+        } finally {
+            if (PRICE_TOKEN_DECIMALS !== undefined) {
+                process.env.PRICE_TOKEN_DECIMALS = PRICE_TOKEN_DECIMALS;
+            }
+        }
+    });
+});
+
+describe('toAtomicUnits', () => {
+    it('should scale decimal amounts to the smallest unit', () => {
+        expect(toAtomicUnits('0.50', 6)).toBe(BigInt(500000));
+        expect(toAtomicUnits('2', 18)).toBe(BigInt('2000000000000000000'));
+    });
+
+    it('should reject malformed or over-precise amounts', () => {
+        expect(toAtomicUnits('abc', 6)).toBeUndefined();
+        expect(toAtomicUnits('0.1234567', 6)).toBeUndefined();
+    });
+});
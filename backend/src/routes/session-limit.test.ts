@@ -0,0 +1,48 @@
+import request from 'supertest';
+import { createApp } from '../server';
+
+process.env.NODE_ENV = 'test';
+
+// A file of its own: the session limiter's counters live for the whole module
+const app = createApp();
+
+describe('POST /api/chat session query limit', () => {
+    const { SESSION_QUERY_LIMIT } = process.env;
+
+    beforeAll(() => {
+        process.env.SESSION_QUERY_LIMIT = '2';
+    });
+
+    afterAll(() => {
+        if (SESSION_QUERY_LIMIT === undefined) {
+            delete process.env.SESSION_QUERY_LIMIT;
+        } else {
+            process.env.SESSION_QUERY_LIMIT = SESSION_QUERY_LIMIT;
+        }
+    });
+
+    const chat = (sessionId?: string) => request(app)
+        .post('/api/chat')
+        .send(sessionId ? { message: 'Hello', sessionId } : { message: 'Hello' });
+
+    it('should answer 429 with Retry-After once a session is over its limit', async () => {
+        const { sessionId } = (await chat()).body;
+
+        expect((await chat(sessionId)).status).toBe(402);
+        expect((await chat(sessionId)).status).toBe(402);
+        const limited = await chat(sessionId);
+        expect(limited.status).toBe(429);
+        expect(limited.body.error).toMatch(/Too many queries on this session/);
+        expect(Number(limited.headers['retry-after'])).toBeGreaterThan(0);
+
+        // Other sessions keep their own budget
+        const other = (await chat()).body.sessionId;
+        expect((await chat(other)).status).toBe(402);
+    });
+
+    it('should not limit requests that send no sessionId', async () => {
+        for (let i = 0; i < 4; i++) {
+            expect((await chat()).status).toBe(402);
+        }
+    });
+});
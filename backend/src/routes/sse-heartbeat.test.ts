@@ -0,0 +1,47 @@
+import request from 'supertest';
+import { createApp } from '../server';
+import { DefaultAgent, StreamEvent } from '../agent/base-agent';
+
+process.env.NODE_ENV = 'test';
+
+describe('POST /api/chat heartbeat', () => {
+    const { SSE_HEARTBEAT_MS } = process.env;
+
+    afterEach(() => {
+        jest.restoreAllMocks();
+        if (SSE_HEARTBEAT_MS === undefined) {
+            delete process.env.SSE_HEARTBEAT_MS;
+        } else {
+            process.env.SSE_HEARTBEAT_MS = SSE_HEARTBEAT_MS;
+        }
+    });
+
+    it('should send heartbeat comments during a slow answer and stop them after', async () => {
+        process.env.SSE_HEARTBEAT_MS = '10';
+        const app = createApp();
+        const chatRes = await request(app).post('/api/chat').send({ message: 'Hello' });
+        const { sessionId } = chatRes.body;
+        await request(app)
+            .post('/api/chat/confirm-payment')
+            .send({ sessionId, txHash: 'abcdef1234567890abcdef1234567890abcdef1234567890abcdef1234567890' });
+
+        // An agent that thinks for several heartbeat intervals before answering
+        jest.spyOn(DefaultAgent.prototype, 'execute').mockImplementation(async function* (): AsyncGenerator<StreamEvent> {
+            await new Promise((resolve) => setTimeout(resolve, 80));
+            yield { type: 'complete', content: 'done' };
+        });
+        const setIntervalSpy = jest.spyOn(global, 'setInterval');
+        const clearIntervalSpy = jest.spyOn(global, 'clearInterval');
+
+        const res = await request(app).post('/api/chat').send({ message: 'Research topic', sessionId });
+
+        expect(res.status).toBe(200);
+        expect(res.text.match(/^: heartbeat$/gm)?.length ?? 0).toBeGreaterThanOrEqual(2);
+        expect(res.text).toContain('"type":"complete"');
+
+        // The heartbeat timer is cleared once the stream ends
+        const heartbeat = setIntervalSpy.mock.calls.findIndex(([, ms]) => ms === 10);
+        expect(heartbeat).toBeGreaterThanOrEqual(0);
+        expect(clearIntervalSpy).toHaveBeenCalledWith(setIntervalSpy.mock.results[heartbeat].value);
+    });
+});
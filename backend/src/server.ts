@@ -6,9 +6,11 @@ import morgan from 'morgan';
 import path from 'path';
 import multer from 'multer';
 import { v4 as uuidv4 } from 'uuid';
-import { SessionStore } from './session/session-store';
+import { SessionStore, sessionTtlMs } from './session/session-store';
+import { CronService } from './cron/cron-service';
 import { createAgentNativeRoutes } from './routes/agent-native';
 import { DefaultAgent } from './agent/base-agent';
+import { CONFIG } from './mx/config';
 
 // [M-2 FIX] Body size limit constant
 const JSON_BODY_LIMIT = '1mb';
@@ -54,8 +56,79 @@ const paymentLimiter = rateLimit({
     message: { error: 'Too many payment confirmation attempts. Please wait.' },
 });
 
-// [M-4 FIX] Verify transaction on-chain (stub — wire to MultiversX API in production)
-async function verifyTransactionOnChain(txHash: string): Promise<{ valid: boolean; status?: string }> {
+// Caps queries on a single session (paid or not) so one payment can't buy unbounded agent runs
+const DEFAULT_SESSION_QUERY_LIMIT = 10;
+
+function sessionQueryLimit(): number {
+    const limit = parseInt(process.env.SESSION_QUERY_LIMIT || '', 10);
+    return Number.isFinite(limit) && limit > 0 ? limit : DEFAULT_SESSION_QUERY_LIMIT;
+}
+
+const sessionQueryLimiter = rateLimit({
+    windowMs: 60 * 1000,
+    max: () => sessionQueryLimit(), // per session per minute
+    keyGenerator: (req) => `session:${req.body?.sessionId}`,
+    skip: (req) => typeof req.body?.sessionId !== 'string',
+    standardHeaders: true,
+    legacyHeaders: false,
+    message: { error: 'Too many queries on this session. Please wait before sending another message.' },
+});
+
+// Converts a human-readable amount ("0.50") to the token's smallest unit
+function toAtomicUnits(amount: string, decimals: number): bigint | undefined {
+    const match = /^(\d+)(?:\.(\d+))?$/.exec(amount.trim());
+    if (!match || (match[2] || '').length > decimals) {
+        return undefined;
+    }
+    return BigInt(match[1] + (match[2] || '').padEnd(decimals, '0'));
+}
+
+// Decimals of tokens the template prices in out of the box
+const KNOWN_TOKEN_DECIMALS: Record<string, number> = { EGLD: 18, 'USDC-350c4e': 6 };
+
+// Decimals of the payment token: pricing.decimals, then PRICE_TOKEN_DECIMALS, then a known token.
+// Undefined when none of them says, and payments in it are then refused rather than guessed at.
+function paymentTokenDecimals(token: string, configured?: unknown): number | undefined {
+    const decimals = Number(configured ?? (process.env.PRICE_TOKEN_DECIMALS || undefined));
+    if (Number.isInteger(decimals) && decimals >= 0) {
+        return decimals;
+    }
+    return KNOWN_TOKEN_DECIMALS[token];
+}
+
+// What a tx from the MultiversX API paid in `token`, in the token's smallest unit
+function paidAtomicUnits(data: Record<string, unknown>, token: string): bigint {
+    if (token === 'EGLD') {
+        return BigInt((data.value as string) || '0');
+    }
+    const action = data.action as { arguments?: { transfers?: Array<Record<string, unknown>> } } | undefined;
+    return (action?.arguments?.transfers || [])
+        .filter((t) => t.token === token)
+        .reduce((sum, t) => sum + BigInt((t.value as string) || '0'), BigInt(0));
+}
+
+// Who a tx from the MultiversX API paid. Multi/NFT ESDT transfers are sent to the sender
+// itself and name the real receiver in the decoded action, so prefer that when present.
+function paymentReceiver(data: Record<string, unknown>): unknown {
+    const action = data.action as { arguments?: { receiver?: string } } | undefined;
+    return action?.arguments?.receiver ?? data.receiver;
+}
+
+// The contract's error message from a failed tx's signalError event, if the API logged one
+function revertReason(data: Record<string, unknown>): string | undefined {
+    const logs = data.logs as { events?: { identifier?: string; topics?: string[] }[] } | undefined;
+    const signalError = logs?.events?.find((e) => e.identifier === 'signalError');
+    const topic = signalError?.topics?.[1];
+    return topic ? Buffer.from(topic, 'base64').toString('utf8') : undefined;
+}
+
+// [M-4 FIX] Verify transaction on-chain: looks the tx up on the MultiversX API (MULTIVERSX_API_URL)
+// and checks its status, receiver and amount against the payment terms
+async function verifyTransactionOnChain(
+    txHash: string,
+    expectedReceiver?: string,
+    expectedPayment?: { amount: string; token: string; decimals?: number },
+): Promise<{ valid: boolean; status?: string; reason?: string }> {
     // In test/dev mode, skip on-chain verification entirely
     if (process.env.NODE_ENV === 'test' || process.env.SKIP_TX_VERIFICATION === 'true') {
         return { valid: true, status: 'skipped_verification' };
@@ -64,17 +137,53 @@ async function verifyTransactionOnChain(txHash: string): Promise<{ valid: boolea
     const apiUrl = process.env.MULTIVERSX_API_URL || 'https://devnet-api.multiversx.com';
     try {
         const response = await fetch(`${apiUrl}/transactions/${txHash}`, { signal: AbortSignal.timeout(10000) });
-        if (!response.ok) {
+        if (response.status === 404) {
             return { valid: false, status: 'not_found' };
         }
+        // Only a gateway-side failure means we can't tell; any other 4xx is a bad hash
+        if (response.status >= 500) {
+            return { valid: false, status: 'chain_unavailable' };
+        }
+        if (!response.ok) {
+            return { valid: false, status: 'invalid' };
+        }
         const data = await response.json() as Record<string, unknown>;
         const status = (data.status as string) || 'unknown';
+        // A tx that failed on-chain moved no funds, whatever its value and receiver say
+        if (status === 'fail' || status === 'invalid') {
+            return { valid: false, status: 'reverted', reason: revertReason(data) };
+        }
+        // A successful tx that paid someone else is not a payment to this agent
+        if (expectedReceiver && paymentReceiver(data) !== expectedReceiver) {
+            return { valid: false, status: 'wrong_receiver' };
+        }
+        // Paying part of the price does not buy the query
+        if (expectedPayment) {
+            // Without the token's decimals the price can't be scaled, so nothing counts as paid
+            if (expectedPayment.decimals === undefined) {
+                return { valid: false, status: 'unknown_decimals' };
+            }
+            const paid = paidAtomicUnits(data, expectedPayment.token);
+            const required = toAtomicUnits(expectedPayment.amount, expectedPayment.decimals);
+            if (required === undefined || paid < required) {
+                return { valid: false, status: 'insufficient_amount' };
+            }
+        }
         return { valid: status === 'success', status };
     } catch {
-        return { valid: false, status: 'verification_failed' };
+        // Unreachable API or timeout: the payment may well be valid, we just can't tell
+        return { valid: false, status: 'chain_unavailable' };
     }
 }
 
+const DEFAULT_SSE_HEARTBEAT_MS = 15 * 1000;
+
+/** Gap between `: heartbeat` comments on a chat stream, overridable through SSE_HEARTBEAT_MS. */
+function sseHeartbeatMs(): number {
+    const interval = parseInt(process.env.SSE_HEARTBEAT_MS || '', 10);
+    return Number.isFinite(interval) && interval > 0 ? interval : DEFAULT_SSE_HEARTBEAT_MS;
+}
+
 // Load agent config
 function loadAgentConfig(): Record<string, unknown> {
     try {
@@ -91,9 +200,24 @@ function loadAgentConfig(): Record<string, unknown> {
     }
 }
 
-export function createApp(): Express {
+// Drop unpaid sessions once SESSION_TTL_MS passes, sweeping at least every minute.
+// The timer never holds the process open; stopAll() on the returned service ends it early.
+export function startSessionCleanup(sessionStore: SessionStore): CronService {
+    const ttlMs = sessionTtlMs();
+    const cron = new CronService();
+    cron.register({
+        name: 'session-cleanup',
+        intervalMs: Math.min(ttlMs, 60 * 1000),
+        handler: () => {
+            sessionStore.cleanExpired(ttlMs);
+        },
+    });
+    cron.startAll();
+    return cron;
+}
+
+export function createApp(sessionStore: SessionStore = new SessionStore()): Express {
     const app = express();
-    const sessionStore = new SessionStore();
     const agentConfig = loadAgentConfig();
 
     // ==========================================
@@ -155,13 +279,41 @@ export function createApp(): Express {
             description: (config.description as string) || '',
             pricing: config.pricing || { perQuery: '0.50', token: 'USDC-350c4e' },
             services: config.services || [],
+            metadataUri: config.metadataUri as string | undefined,
+            address: process.env.AGENT_WALLET_ADDRESS,
+            chainId: CONFIG.CHAIN_ID,
         });
     });
 
+    // Payment terms shared by the 402 gate and the quote endpoint
+    const paymentTerms = () => {
+        const pricing = (agentConfig as Record<string, unknown>).pricing as Record<string, string> | undefined;
+        return {
+            amount: pricing?.perQuery || '0.50',
+            token: pricing?.token || 'USDC-350c4e',
+            receiver: process.env.AGENT_WALLET_ADDRESS || 'erd1...',
+            message: 'Payment required to proceed. Sign the transaction to start your query.',
+        };
+    };
+
     // ==========================================
-    // POST /api/chat [H-1 FIX: Rate limited]
+    // POST /api/chat/quote — price check without opening a session
     // ==========================================
-    app.post('/api/chat', chatLimiter, (req, res) => {
+    app.post('/api/chat/quote', chatLimiter, (req, res) => {
+        const { message } = req.body;
+
+        if (!message || typeof message !== 'string') {
+            res.status(400).json({ error: 'message is required and must be a string' });
+            return;
+        }
+
+        res.json({ payment: paymentTerms() });
+    });
+
+    // ==========================================
+    // POST /api/chat [H-1 FIX: Rate limited per IP and per session]
+    // ==========================================
+    app.post('/api/chat', chatLimiter, sessionQueryLimiter, (req, res) => {
         const { message, sessionId } = req.body;
 
         if (!message || typeof message !== 'string') {
@@ -186,15 +338,9 @@ export function createApp(): Express {
 
         // Payment gate
         if (!session.isPaid) {
-            const pricing = (agentConfig as Record<string, unknown>).pricing as Record<string, string> | undefined;
             res.status(402).json({
                 sessionId: session.id,
-                payment: {
-                    amount: pricing?.perQuery || '0.50',
-                    token: pricing?.token || 'USDC-350c4e',
-                    receiver: process.env.AGENT_WALLET_ADDRESS || 'erd1...',
-                    message: 'Payment required to proceed. Sign the transaction to start your query.',
-                },
+                payment: paymentTerms(),
             });
             return;
         }
@@ -211,6 +357,10 @@ export function createApp(): Express {
             previousMessages: session.messages.slice(0, -1), // exclude current
         };
 
+        // Keep-alive comments so proxies don't time out a long research stream
+        const heartbeat = setInterval(() => res.write(': heartbeat\n\n'), sseHeartbeatMs());
+        heartbeat.unref();
+
         // Stream agent events to client
         (async () => {
             try {
@@ -226,6 +376,7 @@ export function createApp(): Express {
                 const errorMsg = err instanceof Error ? err.message : 'Unknown error';
                 res.write(`data: ${JSON.stringify({ type: 'error', content: errorMsg })}\n\n`);
             } finally {
+                clearInterval(heartbeat);
                 res.end();
             }
         })();
@@ -254,8 +405,49 @@ export function createApp(): Express {
             return;
         }
 
+        // One payment unlocks one session: a tx already settled elsewhere can't be replayed
+        const settledElsewhere = () => {
+            const settled = sessionStore.findByTxHash(txHash);
+            return settled !== undefined && settled.id !== sessionId;
+        };
+        const rejectReuse = () => {
+            res.status(409).json({
+                error: 'Transaction already used to pay for another session',
+                status: 'tx_already_used',
+                txHash,
+            });
+        };
+        if (settledElsewhere()) {
+            rejectReuse();
+            return;
+        }
+
         // [M-4 FIX] Verify tx on-chain
-        const verification = await verifyTransactionOnChain(txHash);
+        const { amount, token } = paymentTerms();
+        const pricing = (agentConfig as Record<string, unknown>).pricing as Record<string, unknown> | undefined;
+        const decimals = paymentTokenDecimals(token, pricing?.decimals);
+        const verification = await verifyTransactionOnChain(
+            txHash,
+            process.env.AGENT_WALLET_ADDRESS,
+            { amount, token, decimals },
+        );
+        if (verification.status === 'chain_unavailable') {
+            res.status(503).json({
+                error: 'Chain unavailable: payment could not be verified, please retry',
+                status: verification.status,
+                txHash,
+            });
+            return;
+        }
+        if (verification.status === 'reverted') {
+            res.status(400).json({
+                error: `Payment transaction reverted on-chain: ${verification.reason || 'no reason given'}`,
+                status: verification.status,
+                reason: verification.reason,
+                txHash,
+            });
+            return;
+        }
         if (!verification.valid) {
             res.status(400).json({
                 error: 'Transaction verification failed',
@@ -265,6 +457,11 @@ export function createApp(): Express {
             return;
         }
 
+        // Another confirm for the same tx may have settled while we awaited the chain
+        if (settledElsewhere()) {
+            rejectReuse();
+            return;
+        }
         const jobId = `job-${uuidv4()}`;
         sessionStore.markPaid(sessionId, txHash, jobId);
 
@@ -326,12 +523,14 @@ export function createApp(): Express {
 }
 
 // Export for testing
-export { sanitizeFilename, verifyTransactionOnChain };
+export { paymentTokenDecimals, sanitizeFilename, toAtomicUnits, verifyTransactionOnChain };
 
 // Start server if run directly
 if (require.main === module) {
     const port = parseInt(process.env.BACKEND_PORT || '4000', 10);
-    const app = createApp();
+    const sessionStore = new SessionStore();
+    const app = createApp(sessionStore);
+    startSessionCleanup(sessionStore);
     app.listen(port, () => {
         console.log(`🚀 mx-openclaw-template-solution backend running on port ${port}`);
     });
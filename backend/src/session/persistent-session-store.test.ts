@@ -89,6 +89,16 @@ describe('PersistentSessionStore', () => {
         });
     });
 
+    describe('findByTxHash', () => {
+        it('should find the session a tx paid for', () => {
+            const paid = store.createSession();
+            store.createSession();
+            store.markPaid(paid.id, 'tx-abc', 'job-123');
+            expect(store.findByTxHash('tx-abc')!.id).toBe(paid.id);
+            expect(store.findByTxHash('tx-unused')).toBeUndefined();
+        });
+    });
+
     describe('addMessage', () => {
         it('should persist messages across restarts', () => {
             const session = store.createSession();
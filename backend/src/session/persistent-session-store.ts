@@ -112,6 +112,14 @@ export class PersistentSessionStore {
         }
     }
 
+    /** The session a payment tx already unlocked, if any — a tx pays for one session only. */
+    findByTxHash(txHash: string): Session | undefined {
+        const row = this.db.prepare('SELECT id FROM sessions WHERE tx_hash = ?').get(txHash) as
+            | { id: string }
+            | undefined;
+        return row ? this.getSession(row.id) : undefined;
+    }
+
     addMessage(id: string, message: ChatMessage): void {
         const session = this.db.prepare('SELECT id FROM sessions WHERE id = ?').get(id);
         if (!session) {
@@ -61,6 +61,20 @@ describe('SessionStore', () => {
         });
     });
 
+    describe('findByTxHash', () => {
+        it('should find the session a tx paid for', () => {
+            const paid = store.createSession();
+            store.createSession();
+            store.markPaid(paid.id, 'tx-hash-123', 'job-456');
+            expect(store.findByTxHash('tx-hash-123')!.id).toBe(paid.id);
+        });
+
+        it('should return undefined for an unused tx', () => {
+            store.createSession();
+            expect(store.findByTxHash('tx-unused')).toBeUndefined();
+        });
+    });
+
     describe('addMessage', () => {
         it('should add a user message to the session', () => {
             const session = store.createSession();
@@ -104,4 +118,23 @@ describe('SessionStore', () => {
             expect(() => store.addFileId('bad-id', 'file-x')).toThrow();
         });
     });
+
+    describe('cleanExpired', () => {
+        it('should remove unpaid sessions older than TTL', () => {
+            const session = store.createSession();
+            session.createdAt = Date.now() - 2 * 60 * 1000;
+            expect(store.cleanExpired(60 * 1000)).toBe(1);
+            expect(store.getSession(session.id)).toBeUndefined();
+        });
+
+        it('should keep fresh and paid sessions', () => {
+            const fresh = store.createSession();
+            const paid = store.createSession();
+            paid.createdAt = Date.now() - 2 * 60 * 1000;
+            store.markPaid(paid.id, 'tx-hash', 'job-1');
+            expect(store.cleanExpired(60 * 1000)).toBe(0);
+            expect(store.getSession(fresh.id)).toBeDefined();
+            expect(store.getSession(paid.id)).toBeDefined();
+        });
+    });
 });
@@ -16,6 +16,15 @@ export interface Session {
     fileIds: string[];
 }
 
+/** Unpaid sessions older than this are dropped (30 minutes). */
+export const DEFAULT_SESSION_TTL_MS = 30 * 60 * 1000;
+
+/** Unpaid-session lifetime, overridable through SESSION_TTL_MS. */
+export function sessionTtlMs(): number {
+    const ttl = parseInt(process.env.SESSION_TTL_MS || '', 10);
+    return Number.isFinite(ttl) && ttl > 0 ? ttl : DEFAULT_SESSION_TTL_MS;
+}
+
 export class SessionStore {
     private sessions: Map<string, Session> = new Map();
 
@@ -45,6 +54,16 @@ export class SessionStore {
         session.jobId = jobId;
     }
 
+    /** The session a payment tx already unlocked, if any — a tx pays for one session only. */
+    findByTxHash(txHash: string): Session | undefined {
+        for (const session of this.sessions.values()) {
+            if (session.txHash === txHash) {
+                return session;
+            }
+        }
+        return undefined;
+    }
+
     addMessage(id: string, message: ChatMessage): void {
         const session = this.sessions.get(id);
         if (!session) {
@@ -71,4 +90,20 @@ export class SessionStore {
     deleteSession(id: string): boolean {
         return this.sessions.delete(id);
     }
+
+    /**
+     * Remove unpaid sessions older than ttlMs milliseconds, i.e. 402s that
+     * were never paid. Paid sessions are kept. Returns how many were removed.
+     */
+    cleanExpired(ttlMs: number): number {
+        const cutoff = Date.now() - ttlMs;
+        let removed = 0;
+        for (const [id, session] of this.sessions) {
+            if (!session.isPaid && session.createdAt < cutoff) {
+                this.sessions.delete(id);
+                removed++;
+            }
+        }
+        return removed;
+    }
 }
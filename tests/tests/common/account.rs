@@ -0,0 +1,233 @@
+//! Account reads from the gateway: nonce, EGLD and ESDT balances, NFTs, code
+//! hash, storage, and token ownership — plus account snapshots the simulator can
+//! restore, standing in for block rollback (which it doesn't offer).
+
+use std::collections::BTreeMap;
+
+use multiversx_sc::types::Address;
+use num_bigint::BigUint;
+
+use super::{
+    address_to_bech32, bech32_to_address, ensure_simulator, get_json, vm_query, HarnessError,
+    GATEWAY_URL,
+};
+
+/// The ESDT system smart contract, which keeps every token's properties.
+pub const ESDT_SYSTEM_SC: &str = "erd1qqqqqqqqqqqqqqqpqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqzllls8a5w6u";
+
+async fn gateway_get(path: &str) -> Result<serde_json::Value, HarnessError> {
+    get_json(&format!("{}{}", GATEWAY_URL, path)).await
+}
+
+fn parse_biguint(value: &serde_json::Value, what: &str) -> Result<BigUint, HarnessError> {
+    value
+        .as_str()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("{} is not a numeric string: {}", what, value),
+        })
+}
+
+/// Raw `data.account` object the gateway reports for `address`.
+pub async fn get_account(address: &Address) -> Result<serde_json::Value, HarnessError> {
+    let resp = gateway_get(&format!("/address/{}", address_to_bech32(address))).await?;
+    let account = &resp["data"]["account"];
+    if account.is_null() {
+        return Err(HarnessError::Gateway {
+            message: format!("account response missing data: {}", resp),
+        });
+    }
+    Ok(account.clone())
+}
+
+/// Shard the gateway assigns to `address`.
+pub async fn get_address_shard(address: &Address) -> Result<u32, HarnessError> {
+    let resp = gateway_get(&format!("/address/{}/shard", address_to_bech32(address))).await?;
+    resp["data"]["shardID"]
+        .as_u64()
+        .map(|shard| shard as u32)
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("shard response missing shardID: {}", resp),
+        })
+}
+
+/// Reads the account nonce the gateway currently reports for `address`.
+pub async fn get_account_nonce(address: &Address) -> Result<u64, HarnessError> {
+    let account = get_account(address).await?;
+    account["nonce"].as_u64().ok_or_else(|| HarnessError::Gateway {
+        message: format!("account response missing nonce: {}", account),
+    })
+}
+
+pub async fn get_account_balance(address: &Address) -> Result<BigUint, HarnessError> {
+    let account = get_account(address).await?;
+    parse_biguint(&account["balance"], "account balance")
+}
+
+/// Fungible ESDT balance; zero when the account holds none of `token_id`.
+pub async fn get_esdt_balance(address: &Address, token_id: &str) -> Result<BigUint, HarnessError> {
+    let resp = gateway_get(&format!(
+        "/address/{}/esdt/{}",
+        address_to_bech32(address),
+        token_id
+    ))
+    .await?;
+    let balance = &resp["data"]["tokenData"]["balance"];
+    if balance.is_null() {
+        return Ok(BigUint::default());
+    }
+    parse_biguint(balance, "ESDT balance")
+}
+
+/// Hash of the code deployed at `contract`, decoded from the gateway's base64
+/// `codeHash`.
+pub async fn get_code_hash(contract: &Address) -> Result<Vec<u8>, HarnessError> {
+    use base64::Engine;
+
+    let account = get_account(contract).await?;
+    let encoded = account["codeHash"]
+        .as_str()
+        .filter(|hash| !hash.is_empty())
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("account has no codeHash: {}", account),
+        })?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map_err(|e| HarnessError::Gateway {
+            message: format!("codeHash {} is not base64: {}", encoded, e),
+        })
+}
+
+/// An NFT held by an account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NftData {
+    pub nonce: u64,
+    pub attributes: Vec<u8>,
+}
+
+/// Identifier of one NFT of a collection: `TOKEN-abcdef-<nonce hex>`, the
+/// nonce as even-length big-endian hex.
+pub fn nft_identifier(token_id: &str, nonce: u64) -> String {
+    let bytes = nonce.to_be_bytes();
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len() - 1);
+    format!("{}-{}", token_id, hex::encode(&bytes[first..]))
+}
+
+/// Every token the account holds (`data.esdts`), keyed by token identifier.
+pub async fn get_account_esdts(address: &Address) -> Result<serde_json::Value, HarnessError> {
+    let resp = gateway_get(&format!("/address/{}/esdt", address_to_bech32(address))).await?;
+    Ok(resp["data"]["esdts"].clone())
+}
+
+/// The NFT `token_id`/`nonce` if `address` holds it.
+pub async fn get_nft(
+    address: &Address,
+    token_id: &str,
+    nonce: u64,
+) -> Result<Option<NftData>, HarnessError> {
+    use base64::Engine;
+
+    let esdts = get_account_esdts(address).await?;
+    let entry = &esdts[nft_identifier(token_id, nonce)];
+    if entry.is_null() {
+        return Ok(None);
+    }
+    let attributes = base64::engine::general_purpose::STANDARD
+        .decode(entry["attributes"].as_str().unwrap_or_default())
+        .map_err(|e| HarnessError::Gateway {
+            message: format!("NFT attributes are not base64: {}", e),
+        })?;
+    Ok(Some(NftData {
+        nonce: entry["nonce"].as_u64().unwrap_or(nonce),
+        attributes,
+    }))
+}
+
+/// Bech32 owner of `token_id`, from the ESDT system contract's
+/// `getTokenProperties` view (name, type, owner, ...). The owner controls the
+/// token's roles.
+pub async fn get_token_owner(token_id: &str) -> Result<String, HarnessError> {
+    let system_sc = bech32_to_address(ESDT_SYSTEM_SC).expect("ESDT system SC address is valid");
+    let properties = vm_query(
+        &system_sc,
+        "getTokenProperties",
+        &[token_id.as_bytes().to_vec()],
+    )
+    .await;
+    properties
+        .get(2)
+        .filter(|owner| owner.len() == 32)
+        .map(|owner| address_to_bech32(&Address::from_slice(owner)))
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("no owner in getTokenProperties for {}", token_id),
+        })
+}
+
+/// Every storage key of `address`, hex key to hex value, from
+/// `/address/{}/keys`. Its length is the contract's storage size in keys.
+pub async fn get_account_storage(
+    address: &Address,
+) -> Result<BTreeMap<String, String>, HarnessError> {
+    let keys = gateway_get(&format!("/address/{}/keys", address_to_bech32(address))).await?;
+    let pairs = keys["data"]["pairs"].as_object().cloned().unwrap_or_default();
+    Ok(pairs
+        .into_iter()
+        .map(|(key, value)| (key, value.as_str().unwrap_or_default().to_string()))
+        .collect())
+}
+
+/// One account's full state — balance, nonce, code and every storage key —
+/// as captured by [`snapshot_account`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountSnapshot {
+    pub address: Address,
+    state: serde_json::Value,
+}
+
+/// Captures `address` in the simulator's set-state format.
+pub async fn snapshot_account(address: &Address) -> Result<AccountSnapshot, HarnessError> {
+    let account = get_account(address).await?;
+    let keys = get_account_storage(address).await?;
+
+    let mut state = serde_json::json!({
+        "address": address_to_bech32(address),
+        "balance": account["balance"],
+        "nonce": account["nonce"],
+        "keys": keys,
+    });
+    for field in ["code", "codeHash", "codeMetadata", "ownerAddress", "developerReward"] {
+        if let Some(value) = account[field].as_str().filter(|v| !v.is_empty()) {
+            state[field] = value.into();
+        }
+    }
+    Ok(AccountSnapshot {
+        address: address.clone(),
+        state,
+    })
+}
+
+/// Puts the account back exactly as snapshotted via
+/// `/simulator/set-state-overwrite`, dropping storage keys written since.
+/// Only that account reverts: tokens it sent elsewhere and other accounts'
+/// state stay as they are.
+pub async fn restore_account(snapshot: &AccountSnapshot) -> Result<(), HarnessError> {
+    let resp = reqwest::Client::new()
+        .post(format!("{}/simulator/set-state-overwrite", GATEWAY_URL))
+        .json(&serde_json::json!([snapshot.state]))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    if !resp.status().is_success() {
+        ensure_simulator("restore_account").await?;
+        return Err(HarnessError::Gateway {
+            message: format!(
+                "set-state-overwrite for {} failed: {}",
+                address_to_bech32(&snapshot.address),
+                resp.status()
+            ),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,80 @@
+//! JSON snapshot of the harness state, for attaching to failure reports.
+
+use super::{
+    address_to_bech32, get_account_balance, get_current_block, scratch_dir, HarnessError,
+    IdentityRegistryInteractor, ReputationRegistryInteractor, TrackedInteractor,
+    ValidationRegistryInteractor,
+};
+
+/// Directory context dumps are written to; a fresh temp dir when unset.
+pub const CONTEXT_DIR_ENV: &str = "OPENCLAW_CONTEXT_DIR";
+
+/// The registries a test deployed; validation and reputation are optional.
+pub struct Registries<'a> {
+    pub identity: &'a IdentityRegistryInteractor,
+    pub validation: Option<&'a ValidationRegistryInteractor>,
+    pub reputation: Option<&'a ReputationRegistryInteractor>,
+}
+
+/// Captures contract addresses, the owner's balance, the agent count, the
+/// current block and the interactor's wallets. Values that can't be read are
+/// recorded as the error text rather than failing the dump.
+pub async fn dump_context(
+    interactor: &TrackedInteractor,
+    registries: &Registries<'_>,
+) -> serde_json::Value {
+    let owner = &registries.identity.wallet_address;
+    let owner_balance = match get_account_balance(owner).await {
+        Ok(balance) => balance.to_string(),
+        Err(e) => e.to_string(),
+    };
+    let block = match get_current_block().await {
+        Ok(block) => serde_json::json!({
+            "nonce": block.nonce,
+            "round": block.round,
+            "epoch": block.epoch,
+            "timestamp": block.timestamp,
+        }),
+        Err(e) => serde_json::Value::String(e.to_string()),
+    };
+
+    serde_json::json!({
+        "contracts": {
+            "identity": address_to_bech32(&registries.identity.contract_address),
+            "validation": registries.validation.map(|r| address_to_bech32(&r.contract_address)),
+            "reputation": registries.reputation.map(|r| address_to_bech32(&r.contract_address)),
+        },
+        "owner": {
+            "address": address_to_bech32(owner),
+            "balance": owner_balance,
+        },
+        "agent_count": registries.identity.get_agent_count().await,
+        "block": block,
+        "wallets": interactor
+            .registered_wallets()
+            .iter()
+            .map(address_to_bech32)
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Writes `context` to `<dir>/<test_name>.json` under `$OPENCLAW_CONTEXT_DIR`
+/// (or a temp dir) and returns the path; call it before failing a test.
+pub fn write_context_dump(
+    test_name: &str,
+    context: &serde_json::Value,
+) -> Result<std::path::PathBuf, HarnessError> {
+    let dir = match std::env::var_os(CONTEXT_DIR_ENV) {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => scratch_dir("openclaw-context"),
+    };
+    let path = dir.join(format!("{}.json", test_name));
+    let io_err = |e: std::io::Error| HarnessError::Io {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+    std::fs::create_dir_all(&dir).map_err(io_err)?;
+    let json = serde_json::to_string_pretty(context).expect("JSON values always serialize");
+    std::fs::write(&path, json).map_err(io_err)?;
+    Ok(path)
+}
@@ -0,0 +1,215 @@
+//! Typed errors returned by the harness helpers.
+
+use std::fmt;
+
+use num_bigint::BigUint;
+
+use super::PendingDiagnosis;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HarnessError {
+    /// A gateway or backend request failed or returned an unusable body.
+    Gateway { message: String },
+    /// The backend answered with a non-success status.
+    Backend { status: u16, body: String },
+    /// A contract call failed on-chain with a `signalError` or similar.
+    ContractRevert { status: u64, message: String },
+    /// `issue_token` was called for a ticker the contract already issued.
+    TokenAlreadyExists(String),
+    /// A tx never left the pending state; `diagnosis` says why.
+    TxPending {
+        tx_hash: String,
+        diagnosis: PendingDiagnosis,
+    },
+    /// The gateway refused a tx because of its nonce.
+    NonceMismatch { tx_nonce: u64, message: String },
+    /// The sender can't cover a tx's value plus its maximum fee.
+    InsufficientFunds { required: BigUint, available: BigUint },
+    /// The gateway kept answering 429; `retry_after` is its last `Retry-After`.
+    RateLimited { retry_after: Option<std::time::Duration> },
+    /// A polled condition did not hold before the deadline.
+    Timeout { what: String, waited_ms: u64 },
+    /// The simulator accepted a block-generation call but the round did not move.
+    ChainNotAdvancing { round_before: u64, round_after: u64 },
+    /// A view consumed more gas than the caller's budget.
+    GasBudgetExceeded {
+        endpoint: String,
+        used: u64,
+        limit: u64,
+    },
+    /// Contract artifacts are missing from the artifacts directory.
+    MissingArtifacts { dir: String, missing: Vec<String> },
+    /// A local file (artifact, fixture, ...) could not be read.
+    Io { path: String, message: String },
+    /// A WASM artifact exceeds the repo's code-size budget.
+    WasmTooLarge {
+        path: String,
+        size: usize,
+        limit: usize,
+    },
+    /// An address expected to be a smart contract belongs to a wallet.
+    NotAContract(String),
+    /// An agent URI doesn't use HTTPS.
+    InsecureUri(String),
+    /// A JSON endpoint answered with something else, e.g. an HTML error page.
+    UnexpectedResponse {
+        url: String,
+        status: u16,
+        content_type: String,
+        body_preview: String,
+    },
+    /// A shell command the harness runs (e.g. to restart the backend) failed.
+    Command { command: String, message: String },
+    /// The gateway runs an older release than the contracts need.
+    GatewayTooOld { version: String, required: String },
+    /// A simulator-only helper was pointed at a real network's gateway.
+    SimulatorOnly { helper: String, gateway: String },
+    /// A replayed scenario failed at `step` (0-based; `steps.len()` for the
+    /// final `expect` checks).
+    Scenario { step: usize, message: String },
+    /// A buffer expected to hold text isn't UTF-8; `hex` is the whole buffer.
+    InvalidUtf8 { valid_up_to: usize, hex: String },
+    /// A response decoded as JSON but a field is missing or malformed.
+    Schema { field: String, message: String },
+    /// A view was refused by the VM; `return_code` is e.g. `function not found`.
+    QueryFailed {
+        endpoint: String,
+        return_code: String,
+        message: String,
+    },
+    /// A CORS preflight from `origin` to `path` was refused or under-permissive.
+    CorsRejected {
+        path: String,
+        origin: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for HarnessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HarnessError::Gateway { message } => write!(f, "gateway error: {}", message),
+            HarnessError::Backend { status, body } => {
+                write!(f, "backend returned {}: {}", status, body)
+            }
+            HarnessError::ContractRevert { status, message } => {
+                write!(f, "contract reverted ({}): {}", status, message)
+            }
+            HarnessError::TokenAlreadyExists(ticker) => {
+                write!(f, "token {} has already been issued", ticker)
+            }
+            HarnessError::NonceMismatch { tx_nonce, message } => {
+                write!(f, "tx nonce {} rejected: {}", tx_nonce, message)
+            }
+            HarnessError::TxPending { tx_hash, diagnosis } => write!(
+                f,
+                "tx {} still pending: {:?} — {}",
+                tx_hash, diagnosis.reason, diagnosis.suggested_action
+            ),
+            HarnessError::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient funds: tx needs {} but the account holds {}",
+                required, available
+            ),
+            HarnessError::RateLimited { retry_after } => match retry_after {
+                Some(wait) => write!(f, "rate limited by the gateway (retry after {:?})", wait),
+                None => write!(f, "rate limited by the gateway"),
+            },
+            HarnessError::Timeout { what, waited_ms } => {
+                write!(f, "timed out after {}ms waiting for {}", waited_ms, what)
+            }
+            HarnessError::ChainNotAdvancing {
+                round_before,
+                round_after,
+            } => write!(
+                f,
+                "chain is not advancing: round {} -> {} after generating a block",
+                round_before, round_after
+            ),
+            HarnessError::GasBudgetExceeded {
+                endpoint,
+                used,
+                limit,
+            } => write!(
+                f,
+                "{} used {} gas, above the {} gas budget",
+                endpoint, used, limit
+            ),
+            HarnessError::MissingArtifacts { dir, missing } => write!(
+                f,
+                "missing WASM artifacts in {}: {} — run tests/setup.sh first",
+                dir,
+                missing.join(", ")
+            ),
+            HarnessError::Io { path, message } => write!(f, "failed to read {}: {}", path, message),
+            HarnessError::WasmTooLarge { path, size, limit } => write!(
+                f,
+                "{} is {} bytes, above the {} byte code-size budget",
+                path, size, limit
+            ),
+            HarnessError::NotAContract(address) => {
+                write!(f, "{} is not a smart contract address", address)
+            }
+            HarnessError::InsecureUri(uri) => write!(f, "agent URI {} is not https://", uri),
+            HarnessError::UnexpectedResponse {
+                url,
+                status,
+                content_type,
+                body_preview,
+            } => write!(
+                f,
+                "{} answered HTTP {} with {} instead of JSON (is the gateway URL right?): {}",
+                url, status, content_type, body_preview
+            ),
+            HarnessError::Command { command, message } => {
+                write!(f, "`{}` failed: {}", command, message)
+            }
+            HarnessError::GatewayTooOld { version, required } => write!(
+                f,
+                "gateway runs {}, but at least {} is required",
+                version, required
+            ),
+            HarnessError::SimulatorOnly { helper, gateway } => write!(
+                f,
+                "{} needs the chain simulator, but {} is a real network gateway",
+                helper, gateway
+            ),
+            HarnessError::Scenario { step, message } => {
+                write!(f, "scenario failed at step {}: {}", step, message)
+            }
+            HarnessError::InvalidUtf8 { valid_up_to, hex } => write!(
+                f,
+                "invalid UTF-8 after {} bytes in 0x{}",
+                valid_up_to, hex
+            ),
+            HarnessError::Schema { field, message } => {
+                write!(f, "invalid `{}` in response: {}", field, message)
+            }
+            HarnessError::QueryFailed {
+                endpoint,
+                return_code,
+                message,
+            } => write!(f, "vm query {} failed ({}): {}", endpoint, return_code, message),
+            HarnessError::CorsRejected {
+                path,
+                origin,
+                reason,
+            } => write!(f, "CORS preflight to {} from {} refused: {}", path, origin, reason),
+        }
+    }
+}
+
+impl HarnessError {
+    /// The contract's error message when this is a [`HarnessError::ContractRevert`].
+    pub fn revert_message(&self) -> Option<&str> {
+        match self {
+            HarnessError::ContractRevert { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+}
+
+impl std::error::Error for HarnessError {}
@@ -0,0 +1,176 @@
+//! HTTP plumbing shared by the helpers: JSON decoding that survives non-JSON
+//! error pages, rate-limit aware retries, transient-failure retries for tx
+//! submission, and a client that injects latency for resilience tests.
+
+use std::time::Duration;
+
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::StatusCode;
+
+use super::HarnessError;
+
+/// Longest body excerpt quoted in [`HarnessError::UnexpectedResponse`].
+const BODY_PREVIEW_CHARS: usize = 200;
+
+/// Retries allowed after a 429 before giving up with
+/// [`HarnessError::RateLimited`].
+pub const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+/// Ceiling on any single wait, whatever `Retry-After` asks for.
+pub const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(10);
+/// First wait when a 429 carries no `Retry-After`; doubles on each retry.
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Retries allowed after a 502/503/504 from `/transaction/send` before giving
+/// up.
+pub const MAX_SUBMIT_RETRIES: u32 = 3;
+/// Ceiling on any single wait between tx submission attempts.
+pub const MAX_SUBMIT_RETRY_WAIT: Duration = Duration::from_secs(2);
+/// First wait after a transient submission failure; doubles on each retry.
+const SUBMIT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Decodes a JSON body. A response declared as something else — typically an
+/// HTML error page from a misconfigured proxy — becomes
+/// [`HarnessError::UnexpectedResponse`] quoting the start of the body instead
+/// of a bare serde error.
+pub async fn read_json(resp: reqwest::Response) -> Result<serde_json::Value, HarnessError> {
+    let url = resp.url().to_string();
+    let status = resp.status().as_u16();
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let body = resp.text().await.map_err(|e| HarnessError::Gateway {
+        message: format!("failed to read {}: {}", url, e),
+    })?;
+
+    if !content_type.is_empty() && !content_type.contains("json") {
+        return Err(HarnessError::UnexpectedResponse {
+            url,
+            status,
+            content_type,
+            body_preview: body.chars().take(BODY_PREVIEW_CHARS).collect(),
+        });
+    }
+    serde_json::from_str(&body).map_err(|e| HarnessError::Gateway {
+        message: format!("failed to parse {}: {}", url, e),
+    })
+}
+
+/// GETs `url` and decodes the body with [`read_json`], waiting out rate
+/// limiting via [`send_with_retry`].
+pub async fn get_json(url: &str) -> Result<serde_json::Value, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = send_with_retry(|| client.get(url)).await?;
+    read_json(resp).await
+}
+
+/// Sends the request built by `build`, retrying on 429. Waits for the
+/// `Retry-After` seconds when given, otherwise backs off exponentially; every
+/// wait is capped at [`MAX_RATE_LIMIT_WAIT`]. Shared simulators rate-limit
+/// bursts of test traffic.
+pub async fn send_with_retry<F>(build: F) -> Result<reqwest::Response, HarnessError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut backoff = RATE_LIMIT_BACKOFF;
+    for attempt in 0..=MAX_RATE_LIMIT_RETRIES {
+        let resp = build().send().await.map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+        if resp.status() != StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+
+        let retry_after = resp
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs);
+        if attempt == MAX_RATE_LIMIT_RETRIES {
+            return Err(HarnessError::RateLimited { retry_after });
+        }
+        let wait = retry_after.unwrap_or(backoff).min(MAX_RATE_LIMIT_WAIT);
+        println!("⚠️ {} rate limited, retrying in {:?}", resp.url(), wait);
+        tokio::time::sleep(wait).await;
+        backoff *= 2;
+    }
+    unreachable!("the last attempt returns")
+}
+
+/// Sends a tx submission built by `build`, retrying when the gateway answers
+/// 502, 503 or 504 — a proxy or node restarting under it. Backoff starts
+/// short and is capped at [`MAX_SUBMIT_RETRY_WAIT`], unlike the read-side
+/// [`send_with_retry`], since a tx waiting to go out holds up its nonce.
+/// Resending is safe: the signed tx is identical, so one that did land the
+/// first time is refused as a duplicate rather than executed twice.
+pub async fn submit_with_retry<F>(build: F) -> Result<reqwest::Response, HarnessError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut backoff = SUBMIT_RETRY_BACKOFF;
+    for attempt in 0..=MAX_SUBMIT_RETRIES {
+        let resp = build().send().await.map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+        let status = resp.status();
+        if !matches!(
+            status,
+            StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE | StatusCode::GATEWAY_TIMEOUT
+        ) {
+            return Ok(resp);
+        }
+
+        if attempt == MAX_SUBMIT_RETRIES {
+            return Err(HarnessError::Gateway {
+                message: format!(
+                    "{} answered {} after {} retries",
+                    resp.url(),
+                    status,
+                    MAX_SUBMIT_RETRIES
+                ),
+            });
+        }
+        let wait = backoff.min(MAX_SUBMIT_RETRY_WAIT);
+        println!(
+            "⚠️ {} answered {}, resubmitting in {:?}",
+            resp.url(),
+            status,
+            wait
+        );
+        tokio::time::sleep(wait).await;
+        backoff *= 2;
+    }
+    unreachable!("the last attempt returns")
+}
+
+/// `reqwest::Client` that waits `delay` before sending each request, to check
+/// retry and timeout handling against a slow backend.
+#[derive(Debug, Clone, Default)]
+pub struct SlowClient {
+    client: reqwest::Client,
+    delay: Duration,
+}
+
+impl SlowClient {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            delay,
+        }
+    }
+
+    pub async fn get(&self, url: &str) -> reqwest::Result<reqwest::Response> {
+        tokio::time::sleep(self.delay).await;
+        self.client.get(url).send().await
+    }
+}
+
+/// Awaits `request`, returning its output along with how long it took.
+pub async fn timed_request<T>(request: impl std::future::Future<Output = T>) -> (T, Duration) {
+    let started = std::time::Instant::now();
+    let output = request.await;
+    (output, started.elapsed())
+}
@@ -0,0 +1,141 @@
+//! Scripted HTTP server for exercising client-side behaviour (timeouts,
+//! retries, malformed responses) without a real backend or gateway.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// One scripted response. Body chunks are written in order, each after its
+/// delay, and the connection is closed at the end. No `Content-Length` is
+/// sent unless set explicitly, so clients read until EOF.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub chunks: Vec<(Duration, Vec<u8>)>,
+}
+
+impl MockResponse {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            chunks: Vec::new(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn body(self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.chunk(Duration::ZERO, bytes)
+    }
+
+    pub fn chunk(mut self, delay: Duration, bytes: impl Into<Vec<u8>>) -> Self {
+        self.chunks.push((delay, bytes.into()));
+        self
+    }
+}
+
+/// Serves the scripted responses in order, one per connection; once the
+/// script runs out the last response is repeated.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<MockResponse>) -> Self {
+        assert!(!responses.is_empty(), "MockServer needs at least one response");
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responses = Arc::new(responses);
+        let served = Arc::new(AtomicUsize::new(0));
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let index = served.fetch_add(1, Ordering::SeqCst);
+                let response = responses[index.min(responses.len() - 1)].clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut stream = stream;
+                    if let Some(head) = read_request(&mut stream).await {
+                        recorded.lock().unwrap().push(head);
+                    }
+                    write_response(stream, response).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Request heads (request line + headers) received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut byte = [0u8; 1024];
+    let head_end = loop {
+        match stream.read(&mut byte).await.ok()? {
+            0 => return None,
+            n => buf.extend_from_slice(&byte[..n]),
+        }
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let content_length = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().ok())
+                .flatten()
+        })
+        .unwrap_or(0);
+
+    // Drain the body so the client isn't cut off mid-send
+    let mut remaining = content_length.saturating_sub(buf.len() - head_end);
+    while remaining > 0 {
+        match stream.read(&mut byte).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => remaining = remaining.saturating_sub(n),
+        }
+    }
+    Some(head)
+}
+
+async fn write_response(mut stream: TcpStream, response: MockResponse) {
+    let mut head = format!("HTTP/1.1 {} Mock\r\nConnection: close\r\n", response.status);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    if stream.write_all(head.as_bytes()).await.is_err() {
+        return;
+    }
+
+    for (delay, bytes) in response.chunks {
+        tokio::time::sleep(delay).await;
+        if stream.write_all(&bytes).await.is_err() || stream.flush().await.is_err() {
+            return;
+        }
+    }
+    let _ = stream.shutdown().await;
+}
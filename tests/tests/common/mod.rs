@@ -5,26 +5,158 @@ use multiversx_sc::proxy_imports::*;
 use multiversx_sc::types::{Address, CodeMetadata, ManagedBuffer};
 use multiversx_sc_snippets::imports::*;
 
+mod account;
+mod context;
+mod error;
+mod http;
+mod mock_server;
+mod nonce;
+mod poll;
+mod reputation;
+mod scenario;
+mod sse;
+mod tx;
+mod validation;
+mod wallets;
+pub use account::*;
+pub use context::*;
+pub use error::*;
+pub use http::*;
+pub use mock_server::*;
+pub use nonce::*;
+pub use poll::*;
+pub use reputation::*;
+pub use scenario::*;
+pub use sse::*;
+pub use tx::*;
+pub use validation::*;
+pub use wallets::*;
+
 pub const GATEWAY_URL: &str = "http://localhost:8085";
-pub const IDENTITY_WASM_PATH: &str = "artifacts/identity-registry.wasm";
-pub const VALIDATION_WASM_PATH: &str = "artifacts/validation-registry.wasm";
-pub const REPUTATION_WASM_PATH: &str = "artifacts/reputation-registry.wasm";
+pub const ARTIFACTS_DIR: &str = "artifacts";
+/// Overrides where contract artifacts are looked up; see [`resolve_artifact`].
+pub const ARTIFACTS_DIR_ENV: &str = "OPENCLAW_ARTIFACTS_DIR";
+pub const IDENTITY_WASM: &str = "identity-registry.wasm";
+pub const VALIDATION_WASM: &str = "validation-registry.wasm";
+pub const REPUTATION_WASM: &str = "reputation-registry.wasm";
+pub const ARTIFACT_FILES: [&str; 3] = [IDENTITY_WASM, VALIDATION_WASM, REPUTATION_WASM];
 
 /// Backend API URL (Express server)
 pub const BACKEND_URL: &str = "http://localhost:4000";
 
+/// URL of a second backend whose `MULTIVERSX_API_URL` points at an
+/// unreachable host, for degraded-mode tests. Those tests skip when unset.
+pub const DEGRADED_BACKEND_URL_ENV: &str = "OPENCLAW_DEGRADED_BACKEND_URL";
+
+/// Origin the web frontend is served from (the backend's `CORS_ORIGIN`).
+/// Override with `OPENCLAW_FRONTEND_ORIGIN`.
+pub const DEFAULT_FRONTEND_ORIGIN: &str = "http://localhost:3000";
+
+/// Balance `fund_default` gives an account, in whole EGLD.
+pub const DEFAULT_FUNDING_EGLD: u64 = 100_000;
+
+/// This repo's size budget for the contract WASM (256 KiB), meant to catch
+/// bloated builds early. It is not a protocol limit; override it with
+/// `OPENCLAW_MAX_WASM_SIZE`.
+pub const WASM_SIZE_BUDGET_BYTES: usize = 256 * 1024;
+
 // ── Chain Simulator Helpers ──
 
+/// What kind of node sits behind [`GATEWAY_URL`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetworkMode {
+    /// The chain simulator, with `/simulator/*` endpoints for set-state and
+    /// block generation.
+    Simulator,
+    /// A real network gateway (localnet, devnet, ...), where blocks come on
+    /// their own schedule and state can only change through txs.
+    Real,
+}
+
+/// Probes `/simulator/observers`, which only the chain simulator serves.
+pub async fn detect_network_mode() -> Result<NetworkMode, HarnessError> {
+    let resp = reqwest::Client::new()
+        .get(format!("{}/simulator/observers", GATEWAY_URL))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    Ok(if resp.status().is_success() {
+        NetworkMode::Simulator
+    } else {
+        NetworkMode::Real
+    })
+}
+
+/// Fails with [`HarnessError::SimulatorOnly`] unless the gateway is the
+/// simulator. Simulator helpers call this once a `/simulator/*` request is
+/// refused, to tell a wrong gateway apart from a broken simulator.
+pub async fn ensure_simulator(helper: &str) -> Result<(), HarnessError> {
+    match detect_network_mode().await? {
+        NetworkMode::Simulator => Ok(()),
+        NetworkMode::Real => Err(HarnessError::SimulatorOnly {
+            helper: helper.to_string(),
+            gateway: GATEWAY_URL.to_string(),
+        }),
+    }
+}
+
+/// Oldest gateway release the contracts' features are known to work on.
+pub const MIN_GATEWAY_VERSION: &str = "v1.6.0";
+
+/// The node software version the gateway reports in `/network/config`,
+/// e.g. `v1.7.13`.
+pub async fn get_gateway_version() -> Result<String, HarnessError> {
+    let body = get_json(&format!("{}/network/config", GATEWAY_URL)).await?;
+    body["data"]["config"]["erd_latest_tag_software_version"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| HarnessError::Schema {
+            field: "erd_latest_tag_software_version".to_string(),
+            message: "missing from /network/config".to_string(),
+        })
+}
+
+/// Parses `v1.7.13`-style versions into `(major, minor, patch)`. The `v` is
+/// optional, missing components count as 0 and pre-release suffixes such as
+/// `-rc1` are ignored.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    let core = version.split(['-', '+']).next()?;
+    let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Fails with [`HarnessError::GatewayTooOld`] unless the gateway reports at
+/// least version `min`.
+pub async fn require_min_version(min: &str) -> Result<(), HarnessError> {
+    let required = parse_version(min).unwrap_or_else(|| panic!("Invalid minimum version {}", min));
+    let version = get_gateway_version().await?;
+    let actual = parse_version(&version).ok_or_else(|| HarnessError::Schema {
+        field: "erd_latest_tag_software_version".to_string(),
+        message: format!("unparseable version {:?}", version),
+    })?;
+    if actual < required {
+        return Err(HarnessError::GatewayTooOld {
+            version,
+            required: min.to_string(),
+        });
+    }
+    Ok(())
+}
+
 pub async fn get_simulator_chain_id() -> String {
     let client = reqwest::Client::new();
-    let resp: serde_json::Value = client
+    let resp = client
         .get(format!("{}/network/config", GATEWAY_URL))
         .send()
         .await
-        .expect("Failed to get network config")
-        .json()
-        .await
-        .expect("Failed to parse network config");
+        .expect("Failed to get network config");
+    let resp = read_json(resp).await.expect("Failed to parse network config");
 
     resp["data"]["config"]["erd_chain_id"]
         .as_str()
@@ -49,6 +181,12 @@ pub async fn fund_address_on_simulator(address_bech32: &str, balance_wei: &str)
 
         match res {
             Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) if resp.status() == reqwest::StatusCode::NOT_FOUND => {
+                if let Err(e) = ensure_simulator("fund_address_on_simulator").await {
+                    panic!("{}", e);
+                }
+                println!("fund_address attempt {} failed: {}", attempt, resp.status())
+            }
             Ok(resp) => println!("fund_address attempt {} failed: {}", attempt, resp.status()),
             Err(e) => println!("fund_address attempt {} error: {}", attempt, e),
         }
@@ -57,6 +195,61 @@ pub async fn fund_address_on_simulator(address_bech32: &str, balance_wei: &str)
     panic!("Failed to fund address after 5 retries");
 }
 
+/// Sets an ESDT balance through the simulator's set-state `esdt` field, so
+/// tests can pre-load the agent token or mock USDC without minting. The
+/// simulator writes the protocol's ESDT storage itself; the account's EGLD
+/// balance and nonce are preserved.
+pub async fn fund_esdt(address_bech32: &str, token_id: &str, amount: &num_bigint::BigUint) {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/address/{}", GATEWAY_URL, address_bech32))
+        .send()
+        .await
+        .expect("Failed to get account");
+    let account = read_json(resp).await.expect("Failed to parse account");
+    let account = &account["data"]["account"];
+
+    let mut esdt = serde_json::Map::new();
+    esdt.insert(token_id.to_string(), amount.to_string().into());
+    let body = serde_json::json!([{
+        "address": address_bech32,
+        "balance": account["balance"].as_str().unwrap_or("0"),
+        "nonce": account["nonce"].as_u64().unwrap_or(0),
+        "esdt": esdt
+    }]);
+
+    let res = client
+        .post(format!("{}/simulator/set-state", GATEWAY_URL))
+        .json(&body)
+        .send()
+        .await
+        .expect("Failed to set ESDT state");
+    if !res.status().is_success() {
+        if let Err(e) = ensure_simulator("fund_esdt").await {
+            panic!("{}", e);
+        }
+    }
+    assert!(res.status().is_success(), "set-state for ESDT failed: {}", res.status());
+}
+
+/// Formats a whole-EGLD amount as the denominated (10^18) string the
+/// simulator expects, e.g. `egld(1)` → `"1000000000000000000"`.
+pub fn egld(amount: u64) -> String {
+    (num_bigint::BigUint::from(amount) * num_bigint::BigUint::from(10u64).pow(18)).to_string()
+}
+
+pub async fn fund_default(address_bech32: &str) {
+    fund_address_on_simulator(address_bech32, &egld(DEFAULT_FUNDING_EGLD)).await;
+}
+
+/// Loads `alice.pem`, registers it with the interactor and funds it with the
+/// default balance. Returns the owner address.
+pub async fn register_and_fund_owner(interactor: &mut impl RegisterWallet) -> Address {
+    let owner = register_wallet(interactor, Wallet::from_pem_file("alice.pem").unwrap());
+    fund_default(&address_to_bech32(&owner)).await;
+    owner
+}
+
 pub async fn generate_blocks_on_simulator(num_blocks: u32) {
     let client = reqwest::Client::new();
     let res = client
@@ -64,14 +257,169 @@ pub async fn generate_blocks_on_simulator(num_blocks: u32) {
         .send()
         .await
         .expect("Failed to generate blocks");
+    if !res.status().is_success() {
+        if let Err(e) = ensure_simulator("generate_blocks_on_simulator").await {
+            panic!("{}", e);
+        }
+    }
     assert!(res.status().is_success(), "generate-blocks failed");
 }
 
+/// Metachain shard id, used for network-wide status queries.
+pub const METACHAIN_SHARD_ID: u32 = 4294967295;
+
+/// Chain height as reported by the metachain's `/network/status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub nonce: u64,
+    pub round: u64,
+    pub epoch: u64,
+    /// Unix seconds of the latest block; 0 on nodes that don't report it.
+    pub timestamp: u64,
+}
+
+pub async fn get_current_block() -> Result<BlockInfo, HarnessError> {
+    let resp = get_json(&format!("{}/network/status/{}", GATEWAY_URL, METACHAIN_SHARD_ID)).await?;
+    let status = &resp["data"]["status"];
+    let field = |key: &str| {
+        status[key].as_u64().ok_or_else(|| HarnessError::Gateway {
+            message: format!("network status missing {}: {}", key, resp),
+        })
+    };
+
+    Ok(BlockInfo {
+        nonce: field("erd_nonce")?,
+        round: field("erd_current_round")?,
+        epoch: field("erd_epoch_number")?,
+        timestamp: status["erd_block_timestamp"].as_u64().unwrap_or(0),
+    })
+}
+
+pub async fn get_current_round() -> Result<u64, HarnessError> {
+    Ok(get_current_block().await?.round)
+}
+
+/// Nonce of the latest block in `shard`.
+pub async fn get_block_nonce(shard: u32) -> Result<u64, HarnessError> {
+    let resp = get_json(&format!("{}/network/status/{}", GATEWAY_URL, shard)).await?;
+    resp["data"]["status"]["erd_nonce"]
+        .as_u64()
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("network status missing erd_nonce: {}", resp),
+        })
+}
+
+/// Generates one block and checks the round actually moved. A wedged simulator
+/// accepts `generate-blocks` without advancing, which otherwise surfaces much
+/// later as confusing tx timeouts.
+pub async fn assert_chain_advancing() -> Result<(), HarnessError> {
+    let round_before = get_current_block().await?.round;
+    generate_blocks_on_simulator(1).await;
+    let round_after = get_current_block().await?.round;
+
+    if round_after <= round_before {
+        return Err(HarnessError::ChainNotAdvancing {
+            round_before,
+            round_after,
+        });
+    }
+    Ok(())
+}
+
+/// Slowest block production a healthy simulator is expected to manage.
+/// Override with `OPENCLAW_MIN_BLOCKS_PER_SEC` on slow CI machines.
+pub const DEFAULT_MIN_BLOCKS_PER_SEC: f64 = 5.0;
+
+/// Minimum rate for [`benchmark_block_generation`]; see [`DEFAULT_MIN_BLOCKS_PER_SEC`].
+pub fn min_blocks_per_sec() -> f64 {
+    std::env::var("OPENCLAW_MIN_BLOCKS_PER_SEC")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MIN_BLOCKS_PER_SEC)
+}
+
+/// Block production rate measured by [`benchmark_block_generation`].
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct BlocksPerSec(pub f64);
+
+impl BlocksPerSec {
+    /// Rate for `blocks` produced in `elapsed`; a zero duration counts as 1ms.
+    pub fn new(blocks: u32, elapsed: std::time::Duration) -> Self {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        Self(blocks as f64 / secs)
+    }
+}
+
+impl std::fmt::Display for BlocksPerSec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.1} blocks/s", self.0)
+    }
+}
+
+/// Times one `generate-blocks` call for `blocks` blocks and returns the
+/// rate. Fails with [`HarnessError::ChainNotAdvancing`] if the nonce didn't
+/// move, since a wedged simulator answers fast without producing anything.
+pub async fn benchmark_block_generation(blocks: u32) -> Result<BlocksPerSec, HarnessError> {
+    let before = get_current_block().await?;
+    let started = std::time::Instant::now();
+    generate_blocks_on_simulator(blocks).await;
+    let elapsed = started.elapsed();
+    let after = get_current_block().await?;
+
+    if after.nonce < before.nonce + blocks as u64 {
+        return Err(HarnessError::ChainNotAdvancing {
+            round_before: before.round,
+            round_after: after.round,
+        });
+    }
+    Ok(BlocksPerSec::new(blocks, elapsed))
+}
+
 pub fn address_to_bech32(address: &Address) -> String {
     let hrp = Hrp::parse("erd").expect("Invalid HRP");
     bech32::encode::<Bech32>(hrp, address.as_bytes()).expect("Failed to encode")
 }
 
+/// Contract addresses start with this many zero bytes, followed by the VM type.
+const SC_ADDRESS_ZERO_PREFIX: usize = 8;
+
+/// Fails with [`HarnessError::NotAContract`] unless `address` carries the
+/// smart-contract marker (leading zero bytes) rather than being a wallet.
+pub fn assert_is_contract_address(address: &Address) -> Result<(), HarnessError> {
+    if address.as_bytes()[..SC_ADDRESS_ZERO_PREFIX].iter().all(|b| *b == 0) {
+        Ok(())
+    } else {
+        Err(HarnessError::NotAContract(address_to_bech32(address)))
+    }
+}
+
+/// VM type of WASM contracts, stored right after the zero prefix.
+const WASM_VM_TYPE: [u8; 2] = [0x05, 0x00];
+
+/// Predicts the address of the contract `deployer` creates with its tx at
+/// `nonce`: keccak256(deployer ++ nonce as u64 LE), with the first bytes
+/// replaced by the zero prefix and VM type and the last two taken from the
+/// deployer so the contract lands in the deployer's shard.
+pub fn compute_contract_address(deployer: &Address, nonce: u64) -> Address {
+    use sha3::{Digest, Keccak256};
+
+    let deployer = deployer.as_bytes();
+    let mut hasher = Keccak256::new();
+    hasher.update(deployer);
+    hasher.update(nonce.to_le_bytes());
+    let mut bytes: [u8; 32] = hasher.finalize().into();
+
+    bytes[..SC_ADDRESS_ZERO_PREFIX].fill(0);
+    bytes[SC_ADDRESS_ZERO_PREFIX..SC_ADDRESS_ZERO_PREFIX + 2].copy_from_slice(&WASM_VM_TYPE);
+    bytes[30..].copy_from_slice(&deployer[30..]);
+    Address::from(bytes)
+}
+
+pub fn bech32_to_address(address_bech32: &str) -> Option<Address> {
+    let (_, bytes) = bech32::decode(address_bech32).ok()?;
+    (bytes.len() == 32).then(|| Address::from_slice(&bytes))
+}
+
 pub fn generate_random_private_key() -> String {
     use rand::RngCore;
     let mut rng = rand::thread_rng();
@@ -80,16 +428,244 @@ pub fn generate_random_private_key() -> String {
     hex::encode(key)
 }
 
+/// Creates a fresh, uniquely named directory under the system temp dir.
+pub fn scratch_dir(prefix: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}-{}", prefix, rand::random::<u64>()));
+    std::fs::create_dir_all(&dir).expect("Failed to create scratch dir");
+    dir
+}
+
+// ── Artifact Checks ──
+
+/// Checks every contract artifact exists in `dir`, naming all missing ones.
+pub fn ensure_artifacts_in(dir: &std::path::Path) -> Result<(), HarnessError> {
+    let missing: Vec<String> = ARTIFACT_FILES
+        .iter()
+        .filter(|name| !dir.join(name).is_file())
+        .map(|name| name.to_string())
+        .collect();
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(HarnessError::MissingArtifacts {
+            dir: dir.display().to_string(),
+            missing,
+        })
+    }
+}
+
+/// Where artifacts are looked up, in order: `$OPENCLAW_ARTIFACTS_DIR` when
+/// set, then `./artifacts`, then `../artifacts`, so tests work from the crate
+/// or the repo root.
+pub fn artifact_search_dirs() -> Vec<std::path::PathBuf> {
+    let mut dirs: Vec<std::path::PathBuf> = std::env::var_os(ARTIFACTS_DIR_ENV)
+        .map(std::path::PathBuf::from)
+        .into_iter()
+        .collect();
+    dirs.push(std::path::PathBuf::from(ARTIFACTS_DIR));
+    dirs.push(std::path::Path::new("..").join(ARTIFACTS_DIR));
+    dirs
+}
+
+/// Path of the artifact `name` in the first search dir that has it.
+pub fn resolve_artifact(name: &str) -> Result<std::path::PathBuf, HarnessError> {
+    let dirs = artifact_search_dirs();
+    dirs.iter()
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+        .ok_or_else(|| HarnessError::MissingArtifacts {
+            dir: dirs
+                .iter()
+                .map(|dir| dir.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" or "),
+            missing: vec![name.to_string()],
+        })
+}
+
+/// Pre-flight check for e2e setup, so a missing build fails with a pointer to
+/// `setup.sh` instead of deep inside a deploy.
+pub fn ensure_artifacts() -> Result<(), HarnessError> {
+    let mut missing = Vec::new();
+    let mut searched = String::new();
+    for name in ARTIFACT_FILES {
+        if let Err(HarnessError::MissingArtifacts { dir, missing: names }) = resolve_artifact(name)
+        {
+            searched = dir;
+            missing.extend(names);
+        }
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(HarnessError::MissingArtifacts {
+            dir: searched,
+            missing,
+        })
+    }
+}
+
+pub fn max_wasm_size() -> usize {
+    std::env::var("OPENCLAW_MAX_WASM_SIZE")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(WASM_SIZE_BUDGET_BYTES)
+}
+
+/// Returns the artifact size in bytes, or an error if it exceeds `limit`.
+pub fn check_wasm_size_with_limit(
+    path: impl AsRef<std::path::Path>,
+    limit: usize,
+) -> Result<usize, HarnessError> {
+    let path = path.as_ref();
+    let size = std::fs::metadata(path)
+        .map_err(|e| HarnessError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?
+        .len() as usize;
+
+    if size > limit {
+        return Err(HarnessError::WasmTooLarge {
+            path: path.display().to_string(),
+            size,
+            limit,
+        });
+    }
+    Ok(size)
+}
+
+pub fn check_wasm_size(path: impl AsRef<std::path::Path>) -> Result<usize, HarnessError> {
+    check_wasm_size_with_limit(path, max_wasm_size())
+}
+
 // ── Backend API Helpers ──
 
 pub async fn backend_health_check() -> bool {
-    let client = reqwest::Client::new();
-    match client.get(format!("{}/api/health", BACKEND_URL)).send().await {
+    health_check_at(&SlowClient::default(), BACKEND_URL).await
+}
+
+/// Whether `{base_url}/api/health` answers with a success status.
+pub async fn health_check_at(client: &SlowClient, base_url: &str) -> bool {
+    match client.get(&format!("{}/api/health", base_url)).await {
         Ok(resp) => resp.status().is_success(),
         Err(_) => false,
     }
 }
 
+/// Frontend origin for CORS checks; see [`DEFAULT_FRONTEND_ORIGIN`].
+pub fn frontend_origin() -> String {
+    std::env::var("OPENCLAW_FRONTEND_ORIGIN").unwrap_or_else(|_| DEFAULT_FRONTEND_ORIGIN.into())
+}
+
+/// Sends the preflight a browser at `origin` makes before a JSON `POST` to
+/// `path`, and requires the backend to allow it: a success status,
+/// `Access-Control-Allow-Origin` of `*` or `origin`, and `POST` plus
+/// `Content-Type` among the allowed methods and headers when listed.
+pub async fn assert_cors_allowed(path: &str, origin: &str) -> Result<(), HarnessError> {
+    cors_allowed_at(BACKEND_URL, path, origin).await
+}
+
+/// [`assert_cors_allowed`] against the backend at `base_url`.
+pub async fn cors_allowed_at(base_url: &str, path: &str, origin: &str) -> Result<(), HarnessError> {
+    let rejected = |reason: String| HarnessError::CorsRejected {
+        path: path.to_string(),
+        origin: origin.to_string(),
+        reason,
+    };
+    let resp = reqwest::Client::new()
+        .request(reqwest::Method::OPTIONS, format!("{}{}", base_url, path))
+        .header(reqwest::header::ORIGIN, origin)
+        .header(reqwest::header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(reqwest::header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    if !resp.status().is_success() {
+        return Err(rejected(format!("preflight answered {}", resp.status())));
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_lowercase)
+    };
+    match header(reqwest::header::ACCESS_CONTROL_ALLOW_ORIGIN) {
+        Some(allowed) if allowed == "*" || allowed == origin.to_lowercase() => {}
+        Some(allowed) => return Err(rejected(format!("only {} is allowed", allowed))),
+        None => return Err(rejected("no Access-Control-Allow-Origin header".into())),
+    }
+    if let Some(methods) = header(reqwest::header::ACCESS_CONTROL_ALLOW_METHODS) {
+        if !methods.split(',').any(|m| m.trim() == "post") {
+            return Err(rejected(format!("POST not among allowed methods {}", methods)));
+        }
+    }
+    if let Some(headers) = header(reqwest::header::ACCESS_CONTROL_ALLOW_HEADERS) {
+        if !headers.split(',').any(|h| matches!(h.trim(), "content-type" | "*")) {
+            return Err(rejected(format!("Content-Type not among allowed headers {}", headers)));
+        }
+    }
+    Ok(())
+}
+
+/// Polls `/api/health` every 500ms until the backend answers or `timeout` passes.
+pub async fn wait_for_backend_ready(timeout: std::time::Duration) -> Result<(), HarnessError> {
+    wait_for_ready_at(&SlowClient::default(), BACKEND_URL, timeout).await
+}
+
+/// Shell command that restarts the backend, e.g. `docker compose restart
+/// backend`. The backend has no restart hook of its own, so restart tests
+/// skip when this is unset.
+pub const BACKEND_RESTART_CMD_ENV: &str = "OPENCLAW_BACKEND_RESTART_CMD";
+
+/// Runs `command` through `sh -c` to restart the backend, then waits up to
+/// `timeout` for it to answer again.
+pub async fn restart_backend(
+    command: &str,
+    timeout: std::time::Duration,
+) -> Result<(), HarnessError> {
+    let command_err = |message: String| HarnessError::Command {
+        command: command.to_string(),
+        message,
+    };
+    let output = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .await
+        .map_err(|e| command_err(e.to_string()))?;
+    if !output.status.success() {
+        return Err(command_err(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    wait_for_backend_ready(timeout).await
+}
+
+/// [`wait_for_backend_ready`] against any server, through `client`.
+pub async fn wait_for_ready_at(
+    client: &SlowClient,
+    base_url: &str,
+    timeout: std::time::Duration,
+) -> Result<(), HarnessError> {
+    poll_until(
+        &format!("{} health", base_url),
+        || async { Ok(health_check_at(client, base_url).await) },
+        |healthy| *healthy,
+        std::time::Duration::from_millis(500),
+        timeout,
+    )
+    .await
+    .map(|_| ())
+}
+
 pub async fn backend_get_agent_profile() -> serde_json::Value {
     let client = reqwest::Client::new();
     client
@@ -102,17 +678,120 @@ pub async fn backend_get_agent_profile() -> serde_json::Value {
         .expect("Failed to parse agent profile")
 }
 
+/// Chain id the backend signs and verifies payments for, as advertised in
+/// `/api/agent`.
+pub async fn backend_get_chain_id() -> String {
+    let profile = backend_get_agent_profile().await;
+    profile["chainId"]
+        .as_str()
+        .unwrap_or_else(|| panic!("Agent profile does not advertise chainId: {}", profile))
+        .to_string()
+}
+
+/// Decimals of the backend's default pricing token (USDC).
+pub const USDC_DECIMALS: u32 = 6;
+
+/// Most fractional digits any supported payment token has (EGLD).
+pub const MAX_TOKEN_DECIMALS: u32 = 18;
+
+/// The agent as advertised by `/api/agent`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentProfile {
+    pub name: String,
+    pub description: String,
+    /// Human-readable price per query, e.g. `"0.50"`.
+    pub price_per_query: String,
+    pub token: String,
+    /// Link to the agent's metadata document or avatar, when configured.
+    pub metadata_uri: Option<String>,
+    /// Bech32 wallet the agent is paid to and registered from, when configured.
+    pub address: Option<String>,
+}
+
+impl AgentProfile {
+    pub fn from_json(body: &serde_json::Value) -> Self {
+        let pricing = &body["pricing"];
+        Self {
+            name: body["name"]
+                .as_str()
+                .expect("Agent profile missing name")
+                .to_string(),
+            description: body["description"].as_str().unwrap_or_default().to_string(),
+            price_per_query: pricing["perQuery"]
+                .as_str()
+                .expect("Agent profile missing pricing.perQuery")
+                .to_string(),
+            token: pricing["token"]
+                .as_str()
+                .expect("Agent profile missing pricing.token")
+                .to_string(),
+            metadata_uri: body["metadataUri"].as_str().map(str::to_string),
+            address: body["address"].as_str().map(str::to_string),
+        }
+    }
+}
+
+/// HEAD-requests the profile's metadata URI and requires a 200. Reaches an
+/// external host, so callers gate it behind the `network-checks` feature.
+pub async fn assert_metadata_resolves(profile: &AgentProfile) -> Result<(), HarnessError> {
+    let uri = profile.metadata_uri.as_deref().ok_or_else(|| HarnessError::Gateway {
+        message: format!("agent {} advertises no metadataUri", profile.name),
+    })?;
+    let resp = reqwest::Client::new()
+        .head(uri)
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: format!("metadata URI {} unreachable: {}", uri, e),
+        })?;
+    if resp.status() != reqwest::StatusCode::OK {
+        return Err(HarnessError::Gateway {
+            message: format!("metadata URI {} answered {}", uri, resp.status()),
+        });
+    }
+    Ok(())
+}
+
+/// Converts a decimal amount such as `"0.50"` into the token's smallest
+/// units. `None` if it isn't a plain decimal or has more than `decimals`
+/// fractional digits.
+pub fn parse_token_amount(amount: &str, decimals: u32) -> Option<num_bigint::BigUint> {
+    let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+    let all_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || !all_digits(whole)
+        || !all_digits(fraction)
+        || fraction.len() > decimals as usize
+    {
+        return None;
+    }
+    let padded = format!("{}{:0<width$}", whole, fraction, width = decimals as usize);
+    padded.parse().ok()
+}
+
 pub async fn backend_start_chat(message: &str) -> serde_json::Value {
+    backend_start_chat_raw(message)
+        .await
+        .json()
+        .await
+        .expect("Failed to parse chat response")
+}
+
+/// Posts `message` to `/api/chat` and hands back the raw response, for tests
+/// that inspect rejections.
+pub async fn backend_start_chat_raw(message: &str) -> reqwest::Response {
+    start_chat_raw_at(BACKEND_URL, message).await
+}
+
+/// [`backend_start_chat_raw`] against the backend at `base_url`.
+pub async fn start_chat_raw_at(base_url: &str, message: &str) -> reqwest::Response {
     let client = reqwest::Client::new();
     client
-        .post(format!("{}/api/chat", BACKEND_URL))
+        .post(format!("{}/api/chat", base_url))
         .json(&serde_json::json!({ "message": message }))
         .send()
         .await
         .expect("Failed to start chat")
-        .json()
-        .await
-        .expect("Failed to parse chat response")
 }
 
 pub async fn backend_confirm_payment(session_id: &str, tx_hash: &str) -> serde_json::Value {
@@ -131,96 +810,1797 @@ pub async fn backend_confirm_payment(session_id: &str, tx_hash: &str) -> serde_j
         .expect("Failed to parse confirmation")
 }
 
-// ── Identity Registry Interactor ──
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfirmResponse {
+    pub status: String,
+    pub job_id: String,
+}
 
-pub struct IdentityRegistryInteractor {
-    pub wallet_address: Address,
-    pub contract_address: Address,
+/// Confirms a payment, returning the backend's rejection as an error instead
+/// of panicking.
+pub async fn try_confirm_payment(
+    session_id: &str,
+    tx_hash: &str,
+) -> Result<ConfirmResponse, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/chat/confirm-payment", BACKEND_URL))
+        .json(&serde_json::json!({
+            "sessionId": session_id,
+            "txHash": tx_hash
+        }))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+
+    let status = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(HarnessError::Backend {
+            status: status.as_u16(),
+            body,
+        });
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&body).map_err(|e| HarnessError::Backend {
+        status: status.as_u16(),
+        body: format!("unparseable confirmation ({}): {}", e, body),
+    })?;
+    Ok(ConfirmResponse {
+        status: json["status"].as_str().unwrap_or_default().to_string(),
+        job_id: json["jobId"].as_str().unwrap_or_default().to_string(),
+    })
 }
 
-impl IdentityRegistryInteractor {
-    pub async fn deploy(interactor: &mut Interactor, wallet_address: Address) -> Self {
-        println!("Deploying Identity Registry...");
-        let wasm_bytes = std::fs::read(IDENTITY_WASM_PATH)
-            .expect("Failed to read identity WASM. Run setup.sh first.");
-        let code_buf = ManagedBuffer::new_from_bytes(&wasm_bytes);
+/// Confirms many `(sessionId, txHash)` pairs concurrently. Results are in the
+/// same order as `pairs`.
+pub async fn confirm_payments_batch(
+    pairs: &[(String, String)],
+) -> Vec<Result<ConfirmResponse, HarnessError>> {
+    futures::future::join_all(
+        pairs
+            .iter()
+            .map(|(session_id, tx_hash)| try_confirm_payment(session_id, tx_hash)),
+    )
+    .await
+}
 
-        interactor.generate_blocks_until_all_activations().await;
+/// Posts a confirmation and returns the HTTP status with the raw JSON body
+/// (`Null` if the body isn't JSON), for tests that care about the exact code.
+pub async fn confirm_payment_raw(session_id: &str, tx_hash: &str) -> (u16, serde_json::Value) {
+    confirm_payment_raw_at(BACKEND_URL, session_id, tx_hash).await
+}
 
-        let contract_address = interactor
-            .tx()
-            .from(&wallet_address)
-            .gas(600_000_000)
-            .raw_deploy()
-            .code(code_buf)
-            .code_metadata(
-                CodeMetadata::UPGRADEABLE
-                    | CodeMetadata::READABLE
-                    | CodeMetadata::PAYABLE
-                    | CodeMetadata::PAYABLE_BY_SC,
-            )
-            .returns(ReturnsNewAddress)
-            .run()
-            .await;
+/// [`confirm_payment_raw`] against the backend at `base_url`.
+pub async fn confirm_payment_raw_at(
+    base_url: &str,
+    session_id: &str,
+    tx_hash: &str,
+) -> (u16, serde_json::Value) {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/chat/confirm-payment", base_url))
+        .json(&serde_json::json!({
+            "sessionId": session_id,
+            "txHash": tx_hash
+        }))
+        .send()
+        .await
+        .expect("Failed to confirm payment");
+    let status = resp.status().as_u16();
+    (status, resp.json().await.unwrap_or_default())
+}
 
-        println!("Identity Registry deployed at: {}", contract_address);
+/// Chat queries the backend accepts on one session per minute
+/// (`SESSION_QUERY_LIMIT`) before answering 429 with `Retry-After`.
+pub const DEFAULT_SESSION_QUERY_LIMIT: u32 = 10;
 
-        Self {
-            wallet_address,
-            contract_address,
-        }
+/// Status of one query on an existing session. `body` is only read for
+/// rejections; a 200 is an SSE stream and is dropped unread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryResponse {
+    pub status: u16,
+    pub retry_after: Option<std::time::Duration>,
+    pub body: String,
+}
+
+/// Posts `message` to `/api/chat` on `session_id` and returns the status,
+/// for tests that probe the per-session rate limit.
+pub async fn backend_send_query_raw(session_id: &str, message: &str) -> QueryResponse {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/chat", BACKEND_URL))
+        .json(&serde_json::json!({ "message": message, "sessionId": session_id }))
+        .send()
+        .await
+        .expect("Failed to send query");
+    let status = resp.status().as_u16();
+    let retry_after = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(std::time::Duration::from_secs);
+    let body = if resp.status().is_success() {
+        String::new()
+    } else {
+        resp.text().await.unwrap_or_default()
+    };
+    QueryResponse {
+        status,
+        retry_after,
+        body,
     }
+}
 
-    pub async fn issue_token(&self, interactor: &mut Interactor, name: &str, ticker: &str) {
-        let name_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(name.as_bytes());
-        let ticker_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(ticker.as_bytes());
+/// Cancels an unpaid session via `DELETE /api/sessions/:id`, so it can no
+/// longer be paid for or used.
+pub async fn backend_cancel_session(session_id: &str) -> Result<(), HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .delete(format!("{}/api/sessions/{}", BACKEND_URL, session_id))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(HarnessError::Backend {
+            status: status.as_u16(),
+            body: resp.text().await.unwrap_or_default(),
+        });
+    }
+    Ok(())
+}
 
-        interactor
-            .tx()
-            .from(&self.wallet_address)
-            .to(&self.contract_address)
-            .gas(600_000_000)
-            .egld(50_000_000_000_000_000u64)
-            .raw_call("issue_token")
-            .argument(&name_buf)
-            .argument(&ticker_buf)
-            .run()
-            .await;
+/// Payment terms from a 402 response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequired {
+    pub session_id: String,
+    pub amount: String,
+    pub token: String,
+    pub recipient: String,
+}
 
-        let _ = interactor.generate_blocks(3).await;
-        println!("Token issued: {}", ticker);
+impl PaymentRequired {
+    /// Decodes a 402 body. The backend names the payee `receiver`; `recipient`
+    /// is accepted too. Fails with [`HarnessError::Schema`] when a field is
+    /// missing or `amount` isn't a non-negative number.
+    pub fn from_json(body: &serde_json::Value) -> Result<Self, HarnessError> {
+        let session_id = required_str(body, "sessionId")?;
+        Self::from_payment(session_id, &body["payment"])
     }
 
-    pub async fn register_agent(
-        &self,
-        interactor: &mut Interactor,
-        name: &str,
-        uri: &str,
-    ) {
-        let name_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(name.as_bytes());
-        let uri_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(uri.as_bytes());
-        let pk_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(&[0u8; 32]);
-        let metadata_count: u32 = 0;
-        let metadata_count_buf: ManagedBuffer<StaticApi> =
-            ManagedBuffer::new_from_bytes(&metadata_count.to_be_bytes());
-        let services_count: u32 = 0;
-        let services_count_buf: ManagedBuffer<StaticApi> =
-            ManagedBuffer::new_from_bytes(&services_count.to_be_bytes());
+    fn from_payment(session_id: &str, payment: &serde_json::Value) -> Result<Self, HarnessError> {
+        let amount = required_str(payment, "amount")?;
+        if parse_token_amount(amount, MAX_TOKEN_DECIMALS).is_none() {
+            return Err(HarnessError::Schema {
+                field: "payment.amount".to_string(),
+                message: format!("{:?} is not a non-negative decimal amount", amount),
+            });
+        }
+        let recipient = match payment["recipient"].as_str() {
+            Some(recipient) => recipient,
+            None => required_str(payment, "receiver")?,
+        };
+        Ok(Self {
+            session_id: session_id.to_string(),
+            amount: amount.to_string(),
+            token: required_str(payment, "token")?.to_string(),
+            recipient: recipient.to_string(),
+        })
+    }
+}
 
-        interactor
+fn required_str<'a>(value: &'a serde_json::Value, field: &str) -> Result<&'a str, HarnessError> {
+    value[field].as_str().ok_or_else(|| HarnessError::Schema {
+        field: field.to_string(),
+        message: "missing or not a string".to_string(),
+    })
+}
+
+/// Fetches the payment terms for `message` from `/api/chat/quote`, which
+/// prices the query without opening a session; `session_id` is left empty.
+pub async fn backend_quote(message: &str) -> PaymentRequired {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/chat/quote", BACKEND_URL))
+        .json(&serde_json::json!({ "message": message }))
+        .send()
+        .await
+        .expect("Failed to request quote");
+    assert!(
+        resp.status().is_success(),
+        "Quote request failed: {}",
+        resp.status()
+    );
+    let body: serde_json::Value = resp.json().await.expect("Failed to parse quote");
+    PaymentRequired::from_payment("", &body["payment"]).expect("Malformed quote")
+}
+
+/// Raw `GET /api/sessions` body: the session count, TTL, and summaries.
+async fn backend_session_list() -> serde_json::Value {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/api/sessions", BACKEND_URL))
+        .send()
+        .await
+        .expect("Failed to list sessions");
+    read_json(resp).await.expect("Failed to parse session list")
+}
+
+/// Number of sessions the backend currently holds, from `GET /api/sessions`.
+pub async fn backend_session_count() -> u64 {
+    backend_session_list().await["count"]
+        .as_u64()
+        .expect("Session list missing count")
+}
+
+/// How long the backend keeps an unpaid session before dropping it
+/// (`SESSION_TTL_MS`, 30 minutes by default). Expired sessions are swept at
+/// least once a minute, so one may outlive its TTL by up to that long.
+pub async fn backend_session_ttl() -> std::time::Duration {
+    let ttl_ms = backend_session_list().await["ttlMs"]
+        .as_u64()
+        .expect("Session list missing ttlMs");
+    std::time::Duration::from_millis(ttl_ms)
+}
+
+/// Whether the backend still knows `session_id`.
+pub async fn backend_session_exists(session_id: &str) -> bool {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/api/sessions/{}", BACKEND_URL, session_id))
+        .send()
+        .await
+        .expect("Failed to get session");
+    match resp.status() {
+        reqwest::StatusCode::NOT_FOUND => false,
+        status if status.is_success() => true,
+        status => panic!("Unexpected status {} for session {}", status, session_id),
+    }
+}
+
+/// Opens an unpaid session via `POST /api/sessions`, which isn't rate limited
+/// like `/api/chat`, and returns its id. Both routes mint ids the same way.
+pub async fn backend_create_session() -> String {
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/api/sessions", BACKEND_URL))
+        .send()
+        .await
+        .expect("Failed to create session");
+    let body = read_json(resp).await.expect("Failed to parse new session");
+    body["sessionId"]
+        .as_str()
+        .expect("New session missing sessionId")
+        .to_string()
+}
+
+/// Fewest bits of estimated entropy a session id may carry; a UUIDv4 has 122.
+pub const MIN_SESSION_ID_BITS: f64 = 96.0;
+
+/// Asserts session ids look unguessable: all distinct, URL-safe, no prefix
+/// shared by every id (counters and timestamps share one), and at least
+/// [`MIN_SESSION_ID_BITS`] of entropy each, estimated from the character
+/// distribution across all ids. Separators (`-`, `_`) don't count.
+pub fn assert_session_id_random(ids: &[String]) {
+    assert!(ids.len() >= 2, "Need at least two session ids to compare");
+
+    let unique: std::collections::HashSet<&String> = ids.iter().collect();
+    assert_eq!(unique.len(), ids.len(), "Session ids repeat");
+    for id in ids {
+        assert!(
+            id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "Session id {:?} has characters outside [A-Za-z0-9_-]",
+            id
+        );
+    }
+
+    let shared_prefix = ids[0]
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| ids.iter().all(|id| id.chars().nth(*i) == Some(*c)))
+        .count();
+    assert!(
+        shared_prefix < 8,
+        "Every session id starts with {:?}; ids look sequential",
+        &ids[0][..shared_prefix]
+    );
+
+    let mut counts = std::collections::HashMap::new();
+    let mut total = 0usize;
+    for c in ids.iter().flat_map(|id| id.chars()).filter(|c| *c != '-' && *c != '_') {
+        *counts.entry(c).or_insert(0usize) += 1;
+        total += 1;
+    }
+    let bits_per_char: f64 = counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum();
+    let shortest = ids
+        .iter()
+        .map(|id| id.chars().filter(|c| *c != '-' && *c != '_').count())
+        .min()
+        .unwrap_or_default();
+    let bits = bits_per_char * shortest as f64;
+    assert!(
+        bits >= MIN_SESSION_ID_BITS,
+        "Session ids carry ~{:.0} bits ({} chars at {:.2} bits each), below {}",
+        bits,
+        shortest,
+        bits_per_char,
+        MIN_SESSION_ID_BITS
+    );
+}
+
+/// Asserts the 402 pays out to `expected` — anything else would route user
+/// funds to an address the agent does not control.
+pub fn assert_payment_recipient(payment: &PaymentRequired, expected: &Address) {
+    let expected_bech32 = address_to_bech32(expected);
+    assert_eq!(
+        payment.recipient, expected_bech32,
+        "402 recipient {} does not match expected {}",
+        payment.recipient, expected_bech32
+    );
+}
+
+// ── Backend Job Helpers ──
+
+pub async fn backend_get_job(job_id: &str) -> Result<serde_json::Value, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/api/jobs/{}", BACKEND_URL, job_id))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(HarnessError::Backend {
+            status: status.as_u16(),
+            body: resp.text().await.unwrap_or_default(),
+        });
+    }
+    read_json(resp).await
+}
+
+/// Report location advertised by a job, either top-level or inside `result`.
+/// Relative paths are resolved against the backend URL.
+fn job_report_url(job: &serde_json::Value) -> Option<String> {
+    let url = ["reportUrl", "report_url"].iter().find_map(|key| {
+        job[key]
+            .as_str()
+            .or_else(|| job["result"][key].as_str())
+    })?;
+    if url.starts_with('/') {
+        Some(format!("{}{}", BACKEND_URL, url))
+    } else {
+        Some(url.to_string())
+    }
+}
+
+/// Current job state, or `None` while the backend doesn't know the job yet (404).
+async fn poll_job(job_id: &str) -> Result<Option<serde_json::Value>, HarnessError> {
+    match backend_get_job(job_id).await {
+        Ok(job) => Ok(Some(job)),
+        Err(HarnessError::Backend { status: 404, .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn job_finished(job: &serde_json::Value) -> bool {
+    job["isComplete"] == true || job["status"] == "completed" || job["status"] == "failed"
+}
+
+/// Polls the job until it completes or fails, returning its final state.
+pub async fn wait_for_job_complete(
+    job_id: &str,
+    timeout: std::time::Duration,
+) -> Result<serde_json::Value, HarnessError> {
+    let job = poll_until(
+        &format!("job {}", job_id),
+        || poll_job(job_id),
+        |job| job.as_ref().is_some_and(job_finished),
+        std::time::Duration::from_millis(1000),
+        timeout,
+    )
+    .await?;
+    Ok(job.expect("predicate only accepts known jobs"))
+}
+
+/// Polls the job until it advertises a report URL, returning the URL.
+/// Jobs the backend doesn't know yet (404) are treated as still pending.
+pub async fn wait_for_report(
+    job_id: &str,
+    timeout: std::time::Duration,
+) -> Result<String, HarnessError> {
+    let job = poll_until(
+        &format!("report of job {}", job_id),
+        || poll_job(job_id),
+        |job| {
+            job.as_ref()
+                .is_some_and(|job| job_report_url(job).is_some() || job["status"] == "failed")
+        },
+        std::time::Duration::from_millis(1000),
+        timeout,
+    )
+    .await?
+    .expect("predicate only accepts known jobs");
+
+    job_report_url(&job).ok_or_else(|| HarnessError::Backend {
+        status: 200,
+        body: format!("job {} failed: {}", job_id, job),
+    })
+}
+
+/// Milliseconds spent in each phase of a research session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionTiming {
+    /// Opening the chat until the 402 arrives.
+    pub chat_402_ms: u64,
+    /// Confirming the payment.
+    pub confirm_ms: u64,
+    /// Sending the query until the first streamed token.
+    pub first_token_ms: u64,
+    /// Confirmation until the job publishes its report.
+    pub report_ms: u64,
+}
+
+/// Per-phase budget used when `OPENCLAW_SESSION_BUDGET_MS` is unset.
+pub const DEFAULT_SESSION_BUDGET: SessionTiming = SessionTiming {
+    chat_402_ms: 2_000,
+    confirm_ms: 2_000,
+    first_token_ms: 10_000,
+    report_ms: 120_000,
+};
+
+impl SessionTiming {
+    fn phases(&self) -> [(&'static str, u64); 4] {
+        [
+            ("chat_402", self.chat_402_ms),
+            ("confirm", self.confirm_ms),
+            ("first_token", self.first_token_ms),
+            ("report", self.report_ms),
+        ]
+    }
+
+    /// Parses `chat_402,confirm,first_token,report` milliseconds.
+    pub fn parse_budget(value: &str) -> Option<Self> {
+        let ms: Vec<u64> = value
+            .split(',')
+            .map(|part| part.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        match ms.as_slice() {
+            [chat_402_ms, confirm_ms, first_token_ms, report_ms] => Some(Self {
+                chat_402_ms: *chat_402_ms,
+                confirm_ms: *confirm_ms,
+                first_token_ms: *first_token_ms,
+                report_ms: *report_ms,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The phase budget, overridable through `OPENCLAW_SESSION_BUDGET_MS` in
+    /// [`SessionTiming::parse_budget`] format.
+    pub fn budget() -> Self {
+        std::env::var("OPENCLAW_SESSION_BUDGET_MS")
+            .ok()
+            .and_then(|v| Self::parse_budget(&v))
+            .unwrap_or(DEFAULT_SESSION_BUDGET)
+    }
+
+    /// Phases slower than `budget`, as `phase: actual > budget` lines.
+    pub fn over_budget(&self, budget: &SessionTiming) -> Vec<String> {
+        self.phases()
+            .into_iter()
+            .zip(budget.phases())
+            .filter(|((_, actual), (_, limit))| actual > limit)
+            .map(|((phase, actual), (_, limit))| format!("{}: {}ms > {}ms", phase, actual, limit))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for SessionTiming {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let phases: Vec<String> = self
+            .phases()
+            .iter()
+            .map(|(phase, ms)| format!("{} {}ms", phase, ms))
+            .collect();
+        write!(f, "{}", phases.join(" → "))
+    }
+}
+
+/// Runs `query` through the whole paid flow (402, confirmation, streamed
+/// answer, report) and times each phase. The payment is confirmed with a
+/// random tx hash, so the backend must skip tx verification.
+pub async fn measure_full_session(query: &str) -> Result<SessionTiming, HarnessError> {
+    let (body, chat_402) = timed_request(backend_start_chat(query)).await;
+    let payment = PaymentRequired::from_json(&body)?;
+
+    let tx_hash = hex::encode(rand::random::<[u8; 32]>());
+    let (confirm, confirm_elapsed) =
+        timed_request(try_confirm_payment(&payment.session_id, &tx_hash)).await;
+    let confirm = confirm?;
+    let paid_at = std::time::Instant::now();
+
+    let stats = run_research_session(&payment.session_id, &[query])
+        .await
+        .map_err(|e| match e {
+            StreamError::Http { status, body } => HarnessError::Backend { status, body },
+            other => HarnessError::Gateway {
+                message: format!("research stream failed: {}", other),
+            },
+        })?;
+    let first_token = stats
+        .first()
+        .and_then(|stat| stat.first_token)
+        .ok_or_else(|| HarnessError::Backend {
+            status: 200,
+            body: "research stream carried no tokens".to_string(),
+        })?;
+
+    wait_for_report(&confirm.job_id, std::time::Duration::from_secs(300)).await?;
+    Ok(SessionTiming {
+        chat_402_ms: chat_402.as_millis() as u64,
+        confirm_ms: confirm_elapsed.as_millis() as u64,
+        first_token_ms: first_token.as_millis() as u64,
+        report_ms: paid_at.elapsed().as_millis() as u64,
+    })
+}
+
+pub async fn backend_download_report(url: &str) -> Result<Vec<u8>, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(HarnessError::Backend {
+            status: status.as_u16(),
+            body: resp.text().await.unwrap_or_default(),
+        });
+    }
+    resp.bytes()
+        .await
+        .map(|bytes| bytes.to_vec())
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })
+}
+
+/// Attempts `download_resumable` makes before giving up on a flaky transfer.
+pub const MAX_DOWNLOAD_ATTEMPTS: u32 = 5;
+
+/// Looks up the job's report URL and downloads it to `dest` with
+/// [`download_resumable`]. Returns the number of bytes written.
+pub async fn backend_download_report_resumable(
+    job_id: &str,
+    dest: &std::path::Path,
+) -> Result<u64, HarnessError> {
+    let job = backend_get_job(job_id).await?;
+    let url = job_report_url(&job).ok_or_else(|| HarnessError::Backend {
+        status: 200,
+        body: format!("job {} has no report yet: {}", job_id, job),
+    })?;
+    download_resumable(&url, dest).await
+}
+
+/// Downloads `url` to `dest`. When the connection drops mid-body the download
+/// resumes with `Range: bytes=N-` and appends; a server that ignores the
+/// range (200 instead of 206) restarts the file. The result is checked
+/// against the advertised total size when there is one.
+pub async fn download_resumable(url: &str, dest: &std::path::Path) -> Result<u64, HarnessError> {
+    use std::io::Write;
+
+    let io_err = |e: std::io::Error| HarnessError::Io {
+        path: dest.display().to_string(),
+        message: e.to_string(),
+    };
+    let mut file = std::fs::File::create(dest).map_err(io_err)?;
+    let mut written: u64 = 0;
+    let mut total: Option<u64> = None;
+    let mut complete = false;
+    let client = reqwest::Client::new();
+
+    for attempt in 1..=MAX_DOWNLOAD_ATTEMPTS {
+        let mut request = client.get(url);
+        if written > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", written));
+        }
+        let mut resp = match request.send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                println!("⚠️ Download attempt {} failed: {}", attempt, e);
+                continue;
+            }
+        };
+
+        match resp.status().as_u16() {
+            206 => {
+                // Content-Range: bytes <start>-<end>/<total>
+                total = resp
+                    .headers()
+                    .get(reqwest::header::CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.rsplit_once('/'))
+                    .and_then(|(_, size)| size.parse().ok())
+                    .or(total);
+            }
+            200 => {
+                if written > 0 {
+                    file = std::fs::File::create(dest).map_err(io_err)?;
+                    written = 0;
+                }
+                total = resp.content_length();
+            }
+            status => {
+                return Err(HarnessError::Backend {
+                    status,
+                    body: resp.text().await.unwrap_or_default(),
+                })
+            }
+        }
+
+        let dropped = loop {
+            match resp.chunk().await {
+                Ok(Some(bytes)) => {
+                    file.write_all(&bytes).map_err(io_err)?;
+                    written += bytes.len() as u64;
+                }
+                Ok(None) => break total.is_some_and(|total| written < total),
+                Err(e) => {
+                    println!("⚠️ Download dropped at {} bytes: {}", written, e);
+                    break true;
+                }
+            }
+        };
+        if !dropped {
+            complete = true;
+            break;
+        }
+    }
+
+    file.flush().map_err(io_err)?;
+    if !complete || total.is_some_and(|total| written != total) {
+        return Err(HarnessError::Backend {
+            status: 206,
+            body: format!(
+                "download of {} stopped at {} of {:?} bytes after {} attempts",
+                url, written, total, MAX_DOWNLOAD_ATTEMPTS
+            ),
+        });
+    }
+    Ok(written)
+}
+
+// ── Backend Session Helpers ──
+
+/// Payment state of a backend chat session, as reported by `/api/sessions/:id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaymentState {
+    Unpaid,
+    Paid,
+}
+
+#[derive(Debug, Clone)]
+pub struct SessionState {
+    pub id: String,
+    pub state: PaymentState,
+    pub job_id: Option<String>,
+}
+
+pub async fn backend_get_session(session_id: &str) -> SessionState {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/api/sessions/{}", BACKEND_URL, session_id))
+        .send()
+        .await
+        .expect("Failed to get session");
+    let resp = read_json(resp).await.expect("Failed to parse session");
+
+    let is_paid = resp["isPaid"].as_bool().expect("Session missing isPaid");
+    SessionState {
+        id: resp["sessionId"].as_str().unwrap_or(session_id).to_string(),
+        state: if is_paid {
+            PaymentState::Paid
+        } else {
+            PaymentState::Unpaid
+        },
+        job_id: resp["jobId"].as_str().map(str::to_string),
+    }
+}
+
+/// Asserts the session exists, is not paid and has no job attached.
+pub async fn assert_session_unpaid(session_id: &str) -> SessionState {
+    let session = backend_get_session(session_id).await;
+    assert_eq!(
+        session.state,
+        PaymentState::Unpaid,
+        "Session {} should be unpaid",
+        session_id
+    );
+    assert!(
+        session.job_id.is_none(),
+        "Unpaid session {} should not have a jobId, got {:?}",
+        session_id,
+        session.job_id
+    );
+    session
+}
+
+/// Asserts the session is paid and carries a jobId.
+pub async fn assert_session_paid(session_id: &str) -> SessionState {
+    let session = backend_get_session(session_id).await;
+    assert_eq!(
+        session.state,
+        PaymentState::Paid,
+        "Session {} should be paid",
+        session_id
+    );
+    assert!(
+        session.job_id.is_some(),
+        "Paid session {} should have a jobId",
+        session_id
+    );
+    session
+}
+
+// ── VM Query Helpers ──
+
+/// Runs a read-only view through the gateway's `/vm-values/query` endpoint and
+/// returns the raw return data, one entry per returned value.
+pub async fn vm_query(contract: &Address, endpoint: &str, args: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    vm_query_at(contract, endpoint, args, None).await
+}
+
+/// [`vm_query`] against the contract state as of `block_nonce` (in the
+/// contract's shard), or the latest state when `None`. Historical queries
+/// need a gateway with historical lookup enabled.
+pub async fn vm_query_at(
+    contract: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+    block_nonce: Option<u64>,
+) -> Vec<Vec<u8>> {
+    try_vm_query_at(contract, endpoint, args, block_nonce)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// [`vm_query`], returning a view the VM refuses (e.g. `function not found`)
+/// as [`HarnessError::QueryFailed`] instead of panicking.
+pub async fn try_vm_query(
+    contract: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+) -> Result<Vec<Vec<u8>>, HarnessError> {
+    try_vm_query_at(contract, endpoint, args, None).await
+}
+
+/// [`try_vm_query`] at `block_nonce`; see [`vm_query_at`].
+pub async fn try_vm_query_at(
+    contract: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+    block_nonce: Option<u64>,
+) -> Result<Vec<Vec<u8>>, HarnessError> {
+    use base64::Engine;
+
+    let client = reqwest::Client::new();
+    let body = serde_json::json!({
+        "scAddress": address_to_bech32(contract),
+        "funcName": endpoint,
+        "args": args.iter().map(hex::encode).collect::<Vec<_>>(),
+    });
+    let mut request = client.post(format!("{}/vm-values/query", GATEWAY_URL));
+    if let Some(nonce) = block_nonce {
+        request = request.query(&[("blockNonce", nonce)]);
+    }
+    let resp = request
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: format!("vm query {} failed: {}", endpoint, e),
+        })?;
+    let resp = read_json(resp).await?;
+
+    let data = &resp["data"]["data"];
+    let return_code = data["returnCode"].as_str().unwrap_or_default();
+    if return_code != "ok" {
+        return Err(HarnessError::QueryFailed {
+            endpoint: endpoint.to_string(),
+            return_code: return_code.to_string(),
+            message: data["returnMessage"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+
+    data["returnData"]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .map(|v| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(v.as_str().unwrap_or_default())
+                        .map_err(|e| HarnessError::Schema {
+                            field: "returnData".into(),
+                            message: format!("invalid base64 from {}: {}", endpoint, e),
+                        })
+                })
+                .collect()
+        })
+        .unwrap_or(Ok(Vec::new()))
+}
+
+/// Whether `contract` exposes `endpoint`. Probes it with no arguments: the
+/// VM answers `function not found` only for a missing endpoint, so argument
+/// or permission errors still count as present.
+pub async fn has_endpoint(contract: &Address, endpoint: &str) -> bool {
+    match try_vm_query(contract, endpoint, &[]).await {
+        Err(HarnessError::QueryFailed { return_code, .. }) => return_code != "function not found",
+        Err(e) => panic!("{}", e),
+        Ok(_) => true,
+    }
+}
+
+/// Runs a view and fails if executing it costs more than `gas_limit`.
+///
+/// `/vm-values/query` runs under the node's own per-query ceiling and does not
+/// report consumption, so the cost is measured by simulating the same call via
+/// `/transaction/cost` from `caller`.
+pub async fn query_with_gas_limit(
+    caller: &Address,
+    contract: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+    gas_limit: u64,
+) -> Result<Vec<Vec<u8>>, HarnessError> {
+    let used = estimate_gas(caller, contract, endpoint, args).await?;
+    if used > gas_limit {
+        return Err(HarnessError::GasBudgetExceeded {
+            endpoint: endpoint.to_string(),
+            used,
+            limit: gas_limit,
+        });
+    }
+    Ok(vm_query(contract, endpoint, args).await)
+}
+
+/// Decodes a top-encoded unsigned integer (big-endian, leading zeros
+/// stripped, empty for zero).
+pub fn decode_top_u64(bytes: &[u8]) -> u64 {
+    assert!(bytes.len() <= 8, "Value too large for u64: 0x{}", hex::encode(bytes));
+    bytes.iter().fold(0u64, |acc, b| (acc << 8) | *b as u64)
+}
+
+/// Minimal reader for MultiversX nested encoding: big-endian integers and
+/// u32 length-prefixed buffers.
+pub struct NestedDecoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> NestedDecoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    /// The next `len` bytes; truncated data is a [`HarnessError::Schema`]
+    /// error rather than a panic, since it comes straight from the chain.
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], HarnessError> {
+        let end = self.pos.saturating_add(len);
+        if end > self.bytes.len() {
+            return Err(HarnessError::Schema {
+                field: "nested".into(),
+                message: format!(
+                    "unexpected end of data: need {} bytes at offset {}, have {}",
+                    len,
+                    self.pos,
+                    self.bytes.len()
+                ),
+            });
+        }
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, HarnessError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, HarnessError> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, HarnessError> {
+        Ok(u64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_buffer(&mut self) -> Result<Vec<u8>, HarnessError> {
+        let len = self.read_u32()? as usize;
+        Ok(self.read_bytes(len)?.to_vec())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, HarnessError> {
+        decode_managed_string(&self.read_buffer()?)
+    }
+
+    pub fn read_biguint(&mut self) -> Result<num_bigint::BigUint, HarnessError> {
+        Ok(num_bigint::BigUint::from_bytes_be(&self.read_buffer()?))
+    }
+}
+
+/// Decodes a ManagedBuffer holding text. Binary data fails with
+/// [`HarnessError::InvalidUtf8`], which quotes the bytes in hex, instead of
+/// panicking mid-decode.
+pub fn decode_managed_string(bytes: &[u8]) -> Result<String, HarnessError> {
+    String::from_utf8(bytes.to_vec()).map_err(|e| HarnessError::InvalidUtf8 {
+        valid_up_to: e.utf8_error().valid_up_to(),
+        hex: hex::encode(bytes),
+    })
+}
+
+/// Appends `bytes` to `out` as a nested-encoded (length-prefixed) buffer.
+pub fn encode_nested_buffer(out: &mut Vec<u8>, bytes: &[u8]) {
+    out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    out.extend_from_slice(bytes);
+}
+
+/// A nested-encoded `MetadataEntry`: the key, then the value.
+fn encode_metadata_entry(key: &str, value: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::new();
+    encode_nested_buffer(&mut entry, key.as_bytes());
+    encode_nested_buffer(&mut entry, value);
+    entry
+}
+
+// ── Deploy Helpers ──
+
+/// Deploys `code` from `owner`, passing `args` to the contract's `init`.
+pub async fn deploy_with_args(
+    interactor: &mut Interactor,
+    owner: &Address,
+    code: &[u8],
+    args: &[Vec<u8>],
+) -> Address {
+    deploy_from_bytes(interactor, owner, code, args)
+        .await
+        .unwrap_or_else(|e| panic!("Deploy failed: {}", e))
+}
+
+/// [`deploy_with_args`], returning a failed deploy instead of panicking.
+/// Bytecode the VM can't load (truncated or garbage) comes back as
+/// [`HarnessError::ContractRevert`] with the node's "invalid code" style
+/// message, never as a bogus address.
+pub async fn deploy_from_bytes(
+    interactor: &mut Interactor,
+    owner: &Address,
+    code: &[u8],
+    args: &[Vec<u8>],
+) -> Result<Address, HarnessError> {
+    let code_buf = ManagedBuffer::new_from_bytes(code);
+
+    interactor.generate_blocks_until_all_activations().await;
+
+    let mut deploy = interactor
+        .tx()
+        .from(owner)
+        .gas(600_000_000)
+        .raw_deploy()
+        .code(code_buf)
+        .code_metadata(
+            CodeMetadata::UPGRADEABLE
+                | CodeMetadata::READABLE
+                | CodeMetadata::PAYABLE
+                | CodeMetadata::PAYABLE_BY_SC,
+        );
+    for arg in args {
+        let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+        deploy = deploy.argument(&arg_buf);
+    }
+
+    let address = deploy
+        .returns(ReturnsHandledOrError::new().returns(ReturnsNewAddress))
+        .run()
+        .await
+        .map_err(|err| HarnessError::ContractRevert {
+            status: err.status,
+            message: err.message,
+        })?;
+    assert_is_contract_address(&address)?;
+    Ok(address)
+}
+
+// ── Identity Registry Interactor ──
+
+/// Numeric agent id assigned by the identity registry at registration.
+pub type AgentId = u64;
+
+/// The price of one of an agent's services, as passed to `register_agent`
+/// and `set_service_configs`: `price` units of `token` (at `nonce`, 0 for
+/// fungible tokens).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceConfigInput {
+    pub service_id: u32,
+    pub price: num_bigint::BigUint,
+    pub token: String,
+    pub nonce: u64,
+}
+
+impl ServiceConfigInput {
+    pub fn encode_nested(&self) -> Vec<u8> {
+        let mut out = self.service_id.to_be_bytes().to_vec();
+        encode_nested_buffer(&mut out, &self.price.to_bytes_be());
+        encode_nested_buffer(&mut out, self.token.as_bytes());
+        out.extend_from_slice(&self.nonce.to_be_bytes());
+        out
+    }
+}
+
+/// A stored service price (`EgldOrEsdtTokenPayment`), as returned by
+/// `get_agent_service_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenPayment {
+    pub token_identifier: String,
+    pub token_nonce: u64,
+    pub amount: num_bigint::BigUint,
+}
+
+impl TokenPayment {
+    pub fn decode(bytes: &[u8]) -> Result<Self, HarnessError> {
+        let mut decoder = NestedDecoder::new(bytes);
+        Ok(Self {
+            token_identifier: decoder.read_string()?,
+            token_nonce: decoder.read_u64()?,
+            amount: decoder.read_biguint()?,
+        })
+    }
+}
+
+/// `AgentDetails` as returned by the identity registry's `get_agent` and
+/// `get_agent_details` views. The URI is not part of it; it is only emitted
+/// at registration (see [`RegisteredAgent`]).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentDetails {
+    pub name: String,
+    pub public_key: [u8; 32],
+}
+
+impl AgentDetails {
+    pub fn decode(bytes: &[u8]) -> Self {
+        Self::try_decode(bytes).unwrap_or_else(|e| panic!("Undecodable agent: {}", e))
+    }
+
+    /// [`AgentDetails::decode`], returning a name that isn't UTF-8, a
+    /// public key that isn't 32 bytes or truncated data as an error.
+    pub fn try_decode(bytes: &[u8]) -> Result<Self, HarnessError> {
+        Self::decode_nested(&mut NestedDecoder::new(bytes))
+    }
+
+    pub fn decode_nested(decoder: &mut NestedDecoder) -> Result<Self, HarnessError> {
+        let name = decoder.read_string()?;
+        let pk = decoder.read_buffer()?;
+        let public_key: [u8; 32] =
+            pk.as_slice().try_into().map_err(|_| HarnessError::Schema {
+                field: "public_key".into(),
+                message: format!(
+                    "agent {} key should be 32 bytes, got {} (0x{})",
+                    name,
+                    pk.len(),
+                    hex::encode(&pk)
+                ),
+            })?;
+        Ok(Self { name, public_key })
+    }
+}
+
+/// One `AgentListEntry` of a `get_agents` page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentListEntry {
+    pub nonce: AgentId,
+    pub owner: Address,
+    pub details: AgentDetails,
+}
+
+impl AgentListEntry {
+    /// Decodes a whole page: the entries nested-encoded back to back.
+    pub fn decode_list(bytes: &[u8]) -> Result<Vec<Self>, HarnessError> {
+        let mut decoder = NestedDecoder::new(bytes);
+        let mut entries = Vec::new();
+        while !decoder.is_empty() {
+            entries.push(Self {
+                nonce: decoder.read_u64()?,
+                owner: Address::from_slice(decoder.read_bytes(32)?),
+                details: AgentDetails::decode_nested(&mut decoder)?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/// An agent as announced by the `agentRegistered` event of its
+/// registration tx.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisteredAgent {
+    pub nonce: AgentId,
+    pub owner: Address,
+    pub name: String,
+    pub uri: String,
+}
+
+impl RegisteredAgent {
+    /// Decodes the event from its topics (event name, owner, nonce) and its
+    /// `AgentRegisteredEventData` payload (name, uri).
+    pub fn decode_event(topics: &[Vec<u8>], data: &[u8]) -> Result<Self, HarnessError> {
+        if topics.len() < 3 || topics[1].len() != 32 || topics[2].len() > 8 {
+            return Err(HarnessError::Schema {
+                field: "agentRegistered".into(),
+                message: format!("expected owner and nonce topics, got {} topics", topics.len()),
+            });
+        }
+        let mut decoder = NestedDecoder::new(data);
+        Ok(Self {
+            nonce: decode_top_u64(&topics[2]),
+            owner: Address::from_slice(&topics[1]),
+            name: decoder.read_string()?,
+            uri: decoder.read_string()?,
+        })
+    }
+}
+
+/// The agent registered by `tx_hash`, read back from its `agentRegistered`
+/// event.
+pub async fn registered_agent(tx_hash: &str) -> Result<RegisteredAgent, HarnessError> {
+    let tx = get_transaction(tx_hash).await?;
+    tx_events(&tx)
+        .iter()
+        .map(|event| (event_topics(event), event_data(event)))
+        .find(|(topics, _)| topics.first().is_some_and(|name| name == b"agentRegistered"))
+        .map(|(topics, data)| RegisteredAgent::decode_event(&topics, &data))
+        .unwrap_or_else(|| {
+            Err(HarnessError::Gateway {
+                message: format!("tx {} emitted no agentRegistered event", tx_hash),
+            })
+        })
+}
+
+/// One `field: before -> after` line per field that differs between `a` and `b`.
+pub fn agent_diff(a: &AgentDetails, b: &AgentDetails) -> Vec<String> {
+    let mut diff = Vec::new();
+    if a.name != b.name {
+        diff.push(format!("name: {:?} -> {:?}", a.name, b.name));
+    }
+    if a.public_key != b.public_key {
+        diff.push(format!(
+            "public_key: {} -> {}",
+            hex::encode(a.public_key),
+            hex::encode(b.public_key)
+        ));
+    }
+    diff
+}
+
+/// Asserts two snapshots of an agent match, listing every differing field.
+pub fn assert_agent_eq(a: &AgentDetails, b: &AgentDetails) {
+    let diff = agent_diff(a, b);
+    assert!(
+        diff.is_empty(),
+        "Agent {} changed:\n  {}",
+        a.name,
+        diff.join("\n  ")
+    );
+}
+
+/// URIs announced for more than one of `agents`, each with the names using
+/// it. URIs aren't readable through any view, so this works on the
+/// registration events (see [`registered_agent`]).
+pub fn find_duplicate_uris(agents: &[RegisteredAgent]) -> Vec<(String, Vec<String>)> {
+    let mut by_uri: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+    for agent in agents {
+        by_uri.entry(agent.uri.clone()).or_default().push(agent.name.clone());
+    }
+    by_uri.into_iter().filter(|(_, names)| names.len() > 1).collect()
+}
+
+/// Asserts no two of `agents` were registered with the same URI.
+pub fn assert_unique_uris(agents: &[RegisteredAgent]) {
+    let duplicates = find_duplicate_uris(agents);
+    assert!(
+        duplicates.is_empty(),
+        "Agents share URIs: {}",
+        duplicates
+            .iter()
+            .map(|(uri, names)| format!("{} used by {}", uri, names.join(", ")))
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+}
+
+/// Deploys the identity registry and drives its endpoints and views.
+/// EGLD the ESDT system contract charges to issue a token, paid with
+/// `issue_token`.
+pub const TOKEN_ISSUE_COST: u64 = 50_000_000_000_000_000;
+
+/// signalError text of `issue_token` once the agent token is issued or its
+/// issuance is pending: the framework's `NonFungibleTokenMapper` refuses to
+/// issue over a token id it already holds.
+pub const TOKEN_ALREADY_ISSUED_ERR: &str = "Token ID already set";
+
+pub struct IdentityRegistryInteractor {
+    pub wallet_address: Address,
+    pub contract_address: Address,
+    /// When set, registrations with a non-HTTPS URI are refused locally.
+    pub require_https: bool,
+}
+
+/// Rejects agent URIs that don't use HTTPS, e.g. `https://research.openclaw.io`.
+pub fn assert_secure_uri(uri: &str) -> Result<(), HarnessError> {
+    let is_https = uri
+        .get(..8)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("https://"))
+        && uri.len() > 8;
+    if is_https {
+        Ok(())
+    } else {
+        Err(HarnessError::InsecureUri(uri.to_string()))
+    }
+}
+
+impl IdentityRegistryInteractor {
+    /// Deploys the registry; its constructor takes no arguments.
+    pub async fn deploy(interactor: &mut Interactor, wallet_address: Address) -> Self {
+        println!("Deploying Identity Registry...");
+        let wasm_path = resolve_artifact(IDENTITY_WASM).unwrap_or_else(|e| panic!("{}", e));
+        if let Err(e) = check_wasm_size(&wasm_path) {
+            println!("⚠️ {}", e);
+        }
+        let wasm_bytes =
+            std::fs::read(&wasm_path).expect("Failed to read identity WASM. Run setup.sh first.");
+
+        let contract_address =
+            deploy_with_args(interactor, &wallet_address, &wasm_bytes, &[]).await;
+        println!("Identity Registry deployed at: {}", contract_address);
+
+        Self {
+            wallet_address,
+            contract_address,
+            require_https: false,
+        }
+    }
+
+    /// Issues the agent token, returning the tx hash. The token id is only
+    /// stored by the async callback from the ESDT system contract; see
+    /// [`wait_for_callback`]. Issuing again once the contract holds a token is
+    /// rejected with [`TOKEN_ALREADY_ISSUED_ERR`], surfaced as
+    /// [`HarnessError::TokenAlreadyExists`]; any other revert stays a
+    /// [`HarnessError::ContractRevert`].
+    pub async fn issue_token(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        ticker: &str,
+    ) -> Result<String, HarnessError> {
+        let name_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(name.as_bytes());
+        let ticker_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(ticker.as_bytes());
+
+        let result = interactor
             .tx()
             .from(&self.wallet_address)
             .to(&self.contract_address)
             .gas(600_000_000)
-            .raw_call("register_agent")
+            .egld(TOKEN_ISSUE_COST)
+            .raw_call("issue_token")
             .argument(&name_buf)
-            .argument(&uri_buf)
-            .argument(&pk_buf)
-            .argument(&metadata_count_buf)
-            .argument(&services_count_buf)
+            .argument(&ticker_buf)
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
             .run()
             .await;
 
+        let tx_hash = match result {
+            Ok(tx_hash) => hex::encode(tx_hash.as_bytes()),
+            Err(err) if err.message.trim() == TOKEN_ALREADY_ISSUED_ERR => {
+                return Err(HarnessError::TokenAlreadyExists(ticker.to_string()));
+            }
+            Err(err) => {
+                return Err(HarnessError::ContractRevert {
+                    status: err.status,
+                    message: err.message,
+                });
+            }
+        };
+
+        let _ = interactor.generate_blocks(3).await;
+        println!("Token issued: {}", ticker);
+        Ok(tx_hash)
+    }
+
+    /// Full token identifier (e.g. `OCAGENT-a1b2c3`) from the
+    /// `get_agent_token_id` view once issuance completed; empty before that.
+    pub async fn get_token_id(&self) -> String {
+        let result = vm_query(&self.contract_address, "get_agent_token_id", &[]).await;
+        result
+            .first()
+            .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+            .unwrap_or_default()
+    }
+
+    /// Registers `name` with a zeroed public key and no services, returning
+    /// the tx hash.
+    pub async fn register_agent(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        uri: &str,
+    ) -> Result<String, HarnessError> {
+        self.register_agent_with_pubkey(interactor, name, uri, &[0u8; 32]).await
+    }
+
+    /// Raw `register_agent` arguments: name, uri, public key, an empty
+    /// metadata list, then the services list — each list as a u32 count
+    /// followed by one nested-encoded argument per entry.
+    pub fn register_agent_args(
+        name: &str,
+        uri: &str,
+        public_key: &[u8; 32],
+        services: &[ServiceConfigInput],
+    ) -> Vec<Vec<u8>> {
+        Self::register_agent_args_with_metadata(name, uri, public_key, &[], services)
+    }
+
+    /// [`Self::register_agent_args`] with `metadata` entries, each encoded as
+    /// a nested key followed by a nested value.
+    pub fn register_agent_args_with_metadata(
+        name: &str,
+        uri: &str,
+        public_key: &[u8; 32],
+        metadata: &[(&str, &[u8])],
+        services: &[ServiceConfigInput],
+    ) -> Vec<Vec<u8>> {
+        let mut args = vec![
+            name.as_bytes().to_vec(),
+            uri.as_bytes().to_vec(),
+            public_key.to_vec(),
+            (metadata.len() as u32).to_be_bytes().to_vec(),
+        ];
+        args.extend(metadata.iter().map(|(key, value)| encode_metadata_entry(key, value)));
+        args.push((services.len() as u32).to_be_bytes().to_vec());
+        args.extend(services.iter().map(ServiceConfigInput::encode_nested));
+        args
+    }
+
+    /// Raw `update_agent` arguments: new name, uri and public key, then the
+    /// optional metadata and services lists encoded as in
+    /// [`Self::register_agent_args`]. `None` leaves a list untouched; since
+    /// only trailing arguments can be omitted, services without metadata
+    /// send an empty metadata list.
+    pub fn update_agent_args(
+        name: &str,
+        uri: &str,
+        public_key: &[u8; 32],
+        metadata: Option<&[(&str, &[u8])]>,
+        services: Option<&[ServiceConfigInput]>,
+    ) -> Vec<Vec<u8>> {
+        let mut args = vec![
+            name.as_bytes().to_vec(),
+            uri.as_bytes().to_vec(),
+            public_key.to_vec(),
+        ];
+        if metadata.is_some() || services.is_some() {
+            let metadata = metadata.unwrap_or_default();
+            args.push((metadata.len() as u32).to_be_bytes().to_vec());
+            args.extend(metadata.iter().map(|(key, value)| encode_metadata_entry(key, value)));
+        }
+        if let Some(services) = services {
+            args.push((services.len() as u32).to_be_bytes().to_vec());
+            args.extend(services.iter().map(ServiceConfigInput::encode_nested));
+        }
+        args
+    }
+
+    pub async fn register_agent_with_pubkey(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        uri: &str,
+        public_key: &[u8; 32],
+    ) -> Result<String, HarnessError> {
+        let args = Self::register_agent_args(name, uri, public_key, &[]);
+        self.submit_register_agent(interactor, name, uri, &args).await
+    }
+
+    pub async fn register_agent_with_services(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        uri: &str,
+        services: &[ServiceConfigInput],
+    ) -> Result<String, HarnessError> {
+        let args = Self::register_agent_args(name, uri, &[0u8; 32], services);
+        self.submit_register_agent(interactor, name, uri, &args).await
+    }
+
+    /// Submits `register_agent` with prebuilt `args` (see
+    /// [`Self::register_agent_args`]), skipping the local HTTPS check, and
+    /// returns the tx hash or the contract's revert.
+    pub async fn register_agent_raw(
+        &self,
+        interactor: &mut Interactor,
+        args: &[Vec<u8>],
+    ) -> Result<String, HarnessError> {
+        let mut call = interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("register_agent");
+        for arg in args {
+            let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+            call = call.argument(&arg_buf);
+        }
+        let tx_hash = call
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })?;
+        Ok(hex::encode(tx_hash.as_bytes()))
+    }
+
+    async fn submit_register_agent(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        uri: &str,
+        args: &[Vec<u8>],
+    ) -> Result<String, HarnessError> {
+        if self.require_https {
+            assert_secure_uri(uri)?;
+        }
+
+        let tx_hash = self.register_agent_raw(interactor, args).await?;
         println!("Agent registered: {}", name);
+        Ok(tx_hash)
+    }
+
+    /// Submits `update_agent` for agent `nonce` with prebuilt `args` (see
+    /// [`Self::update_agent_args`]). The endpoint requires the agent NFT, so
+    /// the owner sends it along; the registry hands it back.
+    pub async fn update_agent(
+        &self,
+        interactor: &mut Interactor,
+        nonce: AgentId,
+        args: &[Vec<u8>],
+    ) -> Result<String, HarnessError> {
+        let token_id: TokenIdentifier<StaticApi> =
+            TokenIdentifier::from(self.get_token_id().await.as_str());
+
+        let mut call = interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .single_esdt(&token_id, nonce, &BigUint::from(1u64))
+            .raw_call("update_agent");
+        for arg in args {
+            let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+            call = call.argument(&arg_buf);
+        }
+        let tx_hash = call
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })?;
+        Ok(hex::encode(tx_hash.as_bytes()))
+    }
+
+    /// Points the agent `name` at `uri`, keeping its name, public key,
+    /// metadata and services.
+    pub async fn update_agent_uri(
+        &self,
+        interactor: &mut Interactor,
+        name: &str,
+        uri: &str,
+    ) -> Result<String, HarnessError> {
+        let agent = self
+            .find_agent(name)
+            .await
+            .unwrap_or_else(|| panic!("Agent {} not found", name));
+        let args = Self::update_agent_args(name, uri, &agent.details.public_key, None, None);
+        self.update_agent(interactor, agent.nonce, &args).await
+    }
+
+    /// Upgrades the contract in place with the current identity WASM artifact.
+    pub async fn upgrade(&self, interactor: &mut Interactor) {
+        println!("Upgrading Identity Registry...");
+        let wasm_path = resolve_artifact(IDENTITY_WASM).unwrap_or_else(|e| panic!("{}", e));
+        let wasm_bytes =
+            std::fs::read(&wasm_path).expect("Failed to read identity WASM. Run setup.sh first.");
+        let code_buf = ManagedBuffer::new_from_bytes(&wasm_bytes);
+
+        interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_upgrade()
+            .code(code_buf)
+            .code_metadata(
+                CodeMetadata::UPGRADEABLE
+                    | CodeMetadata::READABLE
+                    | CodeMetadata::PAYABLE
+                    | CodeMetadata::PAYABLE_BY_SC,
+            )
+            .run()
+            .await;
+
+        let _ = interactor.generate_blocks(3).await;
+        println!("Identity Registry upgraded at: {}", self.contract_address);
+    }
+
+    /// The agent holding NFT nonce `nonce`, via `get_agent`; `None` when the
+    /// registry has no such agent.
+    pub async fn query_agent(&self, nonce: AgentId) -> Option<AgentDetails> {
+        self.fetch_agent("get_agent", nonce, None).await
+    }
+
+    /// [`Self::query_agent`] through the `get_agent_details` view.
+    pub async fn get_agent_details(&self, nonce: AgentId) -> Option<AgentDetails> {
+        self.fetch_agent("get_agent_details", nonce, None).await
+    }
+
+    /// The agent as it was at `block_nonce` of the registry's shard, for
+    /// auditing past registry state.
+    pub async fn query_agent_at_block(
+        &self,
+        nonce: AgentId,
+        block_nonce: u64,
+    ) -> Option<AgentDetails> {
+        self.fetch_agent("get_agent", nonce, Some(block_nonce)).await
+    }
+
+    async fn fetch_agent(
+        &self,
+        view: &str,
+        nonce: AgentId,
+        block_nonce: Option<u64>,
+    ) -> Option<AgentDetails> {
+        let args = [nonce.to_be_bytes().to_vec()];
+        match try_vm_query_at(&self.contract_address, view, &args, block_nonce).await {
+            Ok(result) => match result.first() {
+                Some(bytes) if !bytes.is_empty() => Some(AgentDetails::decode(bytes)),
+                _ => None,
+            },
+            // Unknown nonces revert instead of answering empty
+            Err(HarnessError::QueryFailed { return_code, .. }) if return_code == "user error" => {
+                None
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// One page of `get_agents`: up to `size` agents (the contract caps it
+    /// at 100) starting at index `from`.
+    pub async fn get_agents(&self, from: u64, size: u64) -> Vec<AgentListEntry> {
+        let result = vm_query(
+            &self.contract_address,
+            "get_agents",
+            &[from.to_be_bytes().to_vec(), size.to_be_bytes().to_vec()],
+        )
+        .await;
+        result
+            .first()
+            .map(|bytes| {
+                AgentListEntry::decode_list(bytes)
+                    .unwrap_or_else(|e| panic!("Undecodable get_agents page: {}", e))
+            })
+            .unwrap_or_default()
+    }
+
+    /// First agent registered as exactly `name`, scanning [`Self::get_agents`].
+    /// Names are compared byte for byte, so `ResearchBot` never matches
+    /// `researchbot`.
+    pub async fn find_agent(&self, name: &str) -> Option<AgentListEntry> {
+        self.list_all_agents()
+            .await
+            .into_iter()
+            .find(|entry| entry.details.name == name)
+    }
+
+    /// Number of agents currently registered.
+    pub async fn get_agent_count(&self) -> u64 {
+        let result = vm_query(&self.contract_address, "get_agent_count", &[]).await;
+        result.first().map(|bytes| decode_top_u64(bytes)).unwrap_or(0)
+    }
+
+    /// Every `(nonce, owner)` pair from the `get_agent_id` view, which takes
+    /// no arguments and answers with the pairs flattened. Look an agent up by
+    /// name with [`Self::find_agent`].
+    pub async fn get_agent_ids(&self) -> Vec<(AgentId, Address)> {
+        let result = vm_query(&self.contract_address, "get_agent_id", &[]).await;
+        assert!(result.len() % 2 == 0, "get_agent_id returned an odd number of values");
+        result
+            .chunks(2)
+            .map(|pair| (decode_top_u64(&pair[0]), Address::from_slice(&pair[1])))
+            .collect()
+    }
+
+    /// Sets or replaces the prices of agent `nonce`'s services, keyed by
+    /// `service_id`. Only the agent's owner may call it.
+    pub async fn set_service_configs(
+        &self,
+        interactor: &mut Interactor,
+        nonce: AgentId,
+        configs: &[ServiceConfigInput],
+    ) {
+        let mut args = vec![
+            nonce.to_be_bytes().to_vec(),
+            (configs.len() as u32).to_be_bytes().to_vec(),
+        ];
+        args.extend(configs.iter().map(ServiceConfigInput::encode_nested));
+
+        let mut call = interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("set_service_configs");
+        for arg in &args {
+            let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+            call = call.argument(&arg_buf);
+        }
+        call.run().await;
+
+        println!("Service configs set for agent #{}: {}", nonce, configs.len());
+    }
+
+    /// Price of service `service_id` of agent `nonce`, or `None` if the
+    /// agent has no such service.
+    pub async fn get_agent_service_config(
+        &self,
+        nonce: AgentId,
+        service_id: u32,
+    ) -> Option<TokenPayment> {
+        let result = vm_query(
+            &self.contract_address,
+            "get_agent_service_config",
+            &[nonce.to_be_bytes().to_vec(), service_id.to_be_bytes().to_vec()],
+        )
+        .await;
+        result.first().filter(|bytes| !bytes.is_empty()).map(|bytes| {
+            TokenPayment::decode(bytes)
+                .unwrap_or_else(|e| panic!("Undecodable service config: {}", e))
+        })
+    }
+
+    /// Pages through `get_agents` until a short page, returning every agent.
+    pub async fn list_all_agents(&self) -> Vec<AgentListEntry> {
+        const PAGE: u64 = 100;
+        let mut agents = Vec::new();
+        loop {
+            let page = self.get_agents(agents.len() as u64, PAGE).await;
+            let done = page.len() < PAGE as usize;
+            agents.extend(page);
+            if done {
+                return agents;
+            }
+        }
+    }
+
+    /// Asserts `name` is part of the enumerable set, not only reachable
+    /// through `get_agent`. Scans the first `limit` agents.
+    pub async fn assert_agent_listed(&self, name: &str, limit: u64) {
+        let agents = self.get_agents(0, limit).await;
+        assert!(
+            agents.iter().any(|agent| agent.details.name == name),
+            "Agent {} is stored but missing from get_agents(0, {}) ({} agents listed)",
+            name,
+            limit,
+            agents.len()
+        );
+    }
+
+    /// Asserts the backend serves the identity registered on-chain: an agent
+    /// named `profile.name` exists and the profile's address is `registrant`.
+    pub async fn assert_profile_matches(&self, profile: &AgentProfile, registrant: &Address) {
+        let agent = self
+            .find_agent(&profile.name)
+            .await
+            .unwrap_or_else(|| panic!("Backend serves {}, which is not registered", profile.name));
+        assert_eq!(
+            address_to_bech32(&agent.owner),
+            address_to_bech32(registrant),
+            "{} is owned by another wallet",
+            profile.name
+        );
+        assert_eq!(
+            profile.address.as_deref(),
+            Some(address_to_bech32(registrant).as_str()),
+            "Backend address differs from the wallet that registered {}",
+            profile.name
+        );
+    }
+
+    /// Asserts the stored public key matches `expected` byte for byte.
+    pub async fn assert_pubkey(&self, name: &str, expected: &[u8; 32]) {
+        let agent = self
+            .find_agent(name)
+            .await
+            .unwrap_or_else(|| panic!("Agent {} not found", name));
+        assert_eq!(
+            hex::encode(agent.details.public_key),
+            hex::encode(expected),
+            "Public key of agent {} did not round-trip",
+            name
+        );
+    }
+}
+
+/// A funded owner with a fresh identity registry, its agent token issued and
+/// one agent registered: the starting point of most registry tests.
+pub struct RegisteredAgentSetup {
+    pub interactor: Interactor,
+    pub owner: Address,
+    pub identity: IdentityRegistryInteractor,
+    pub agent_id: AgentId,
+    /// Hash of the `register_agent` tx, for tests that inspect it.
+    pub register_tx: String,
+}
+
+/// Deploys the identity registry from a funded owner, issues the agent token
+/// and registers `name` at `https://research.openclaw.io`, then looks the
+/// agent up by name.
+pub async fn setup_registered_agent(name: &str) -> RegisteredAgentSetup {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    let register_tx = identity
+        .register_agent(&mut interactor, name, "https://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let agent_id = identity
+        .find_agent(name)
+        .await
+        .unwrap_or_else(|| panic!("{} not registered", name))
+        .nonce;
+
+    RegisteredAgentSetup {
+        interactor,
+        owner,
+        identity,
+        agent_id,
+        register_tx,
     }
 }
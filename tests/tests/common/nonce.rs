@@ -0,0 +1,61 @@
+//! Local nonce bookkeeping for transactions we sign and submit ourselves.
+
+use std::collections::HashMap;
+
+use multiversx_sc::types::Address;
+
+use super::{address_to_bech32, get_account_nonce, HarnessError};
+
+/// Hands out nonces per sender so several txs can be in flight at once.
+///
+/// The gateway only reports the nonce of the last *processed* tx, so a wallet
+/// that submits faster than blocks are produced would otherwise reuse nonces.
+/// The tracker increments locally and re-syncs whenever the chain has moved
+/// past the cached value (e.g. another interactor used the same wallet).
+#[derive(Debug, Default)]
+pub struct NonceTracker {
+    nonces: HashMap<Address, u64>,
+}
+
+impl NonceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the nonce to use for the next tx from `address` and reserves it.
+    pub async fn next(&mut self, address: &Address) -> Result<u64, HarnessError> {
+        let on_chain = get_account_nonce(address).await?;
+        let nonce = match self.nonces.get(address) {
+            Some(&cached) if cached >= on_chain => cached,
+            Some(&cached) => {
+                println!(
+                    "NonceTracker: {} cached {} behind chain {}, resyncing",
+                    address_to_bech32(address),
+                    cached,
+                    on_chain
+                );
+                on_chain
+            }
+            None => on_chain,
+        };
+        self.nonces.insert(address.clone(), nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Drops the cached value and adopts the gateway's nonce.
+    pub async fn sync(&mut self, address: &Address) -> Result<u64, HarnessError> {
+        let on_chain = get_account_nonce(address).await?;
+        self.nonces.insert(address.clone(), on_chain);
+        Ok(on_chain)
+    }
+
+    /// Overrides the cached nonce for `address`, e.g. to reproduce a desync.
+    pub fn set(&mut self, address: &Address, nonce: u64) {
+        self.nonces.insert(address.clone(), nonce);
+    }
+
+    /// The next nonce the tracker would hand out, without touching the gateway.
+    pub fn peek(&self, address: &Address) -> Option<u64> {
+        self.nonces.get(address).copied()
+    }
+}
@@ -0,0 +1,37 @@
+//! Generic polling used by every `wait_for_*` helper.
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use super::HarnessError;
+
+/// Calls `f` every `interval` until its value satisfies `predicate`, returning
+/// that value. Errors from `f` end the poll immediately; if `timeout` passes
+/// first the result is [`HarnessError::Timeout`] naming `what`.
+pub async fn poll_until<F, Fut, T, P>(
+    what: &str,
+    mut f: F,
+    mut predicate: P,
+    interval: Duration,
+    timeout: Duration,
+) -> Result<T, HarnessError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, HarnessError>>,
+    P: FnMut(&T) -> bool,
+{
+    let started = Instant::now();
+    loop {
+        let value = f().await?;
+        if predicate(&value) {
+            return Ok(value);
+        }
+        if started.elapsed() >= timeout {
+            return Err(HarnessError::Timeout {
+                what: what.to_string(),
+                waited_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -0,0 +1,198 @@
+//! Reputation Registry interactor.
+
+use multiversx_sc::types::{Address, ManagedBuffer};
+use multiversx_sc_snippets::imports::*;
+
+use super::{
+    check_wasm_size, decode_top_u64, deploy_with_args, resolve_artifact, try_vm_query, vm_query,
+    AgentId, HarnessError, IdentityRegistryInteractor, REPUTATION_WASM,
+};
+
+/// Page size used when walking the identity registry's agent list.
+const AGENT_PAGE_SIZE: u64 = 100;
+
+/// Batch view taking agent ids and returning a `(score, jobs)` pair per id,
+/// in argument order.
+const REPUTATIONS_BATCH_VIEW: &str = "get_reputations";
+
+/// An agent's standing in the reputation registry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReputationView {
+    /// Average rating across all feedback, as kept by the contract.
+    pub average: u64,
+    /// Number of rated jobs behind the average.
+    pub total_jobs: u64,
+}
+
+/// Splits a [`REPUTATIONS_BATCH_VIEW`] result into one view per agent; it
+/// must hold exactly two values for each of the `count` agents.
+pub fn decode_reputations_batch(
+    values: &[Vec<u8>],
+    count: usize,
+) -> Result<Vec<ReputationView>, HarnessError> {
+    if values.len() != count * 2 {
+        return Err(HarnessError::Schema {
+            field: REPUTATIONS_BATCH_VIEW.into(),
+            message: format!("{} values for {} agents", values.len(), count),
+        });
+    }
+    Ok(values
+        .chunks(2)
+        .map(|pair| ReputationView {
+            average: decode_top_u64(&pair[0]),
+            total_jobs: decode_top_u64(&pair[1]),
+        })
+        .collect())
+}
+
+/// Checks `view` against the ratings given so far: the job count must match
+/// and the contract's integer average may differ from the true mean of
+/// `ratings` by less than one (truncation or rounding). The contract keeps no
+/// rating sum, so the mean comes from the ratings known locally.
+pub fn check_running_average(view: &ReputationView, ratings: &[u64]) -> Result<(), String> {
+    let count = ratings.len() as u64;
+    let sum: u64 = ratings.iter().sum();
+    if view.total_jobs != count {
+        return Err(format!("total_jobs {} after {} ratings", view.total_jobs, count));
+    }
+    if count == 0 {
+        return Ok(());
+    }
+    let (floor, ceil) = (sum / count, sum.div_ceil(count));
+    if view.average < floor || view.average > ceil {
+        return Err(format!(
+            "average {} outside [{}, {}] for ratings {:?}",
+            view.average, floor, ceil, ratings
+        ));
+    }
+    Ok(())
+}
+
+pub struct ReputationRegistryInteractor {
+    pub wallet_address: Address,
+    pub contract_address: Address,
+}
+
+impl ReputationRegistryInteractor {
+    /// Deploys the registry bound to the validation and identity registries;
+    /// only the validation registry may push reputation updates.
+    pub async fn deploy(
+        interactor: &mut Interactor,
+        wallet_address: Address,
+        validation_address: &Address,
+        identity_address: &Address,
+    ) -> Self {
+        println!("Deploying Reputation Registry...");
+        let wasm_path = resolve_artifact(REPUTATION_WASM).unwrap_or_else(|e| panic!("{}", e));
+        if let Err(e) = check_wasm_size(&wasm_path) {
+            println!("⚠️ {}", e);
+        }
+        let wasm_bytes = std::fs::read(&wasm_path)
+            .expect("Failed to read reputation WASM. Run setup.sh first.");
+
+        let args = [
+            validation_address.as_bytes().to_vec(),
+            identity_address.as_bytes().to_vec(),
+        ];
+        let contract_address =
+            deploy_with_args(interactor, &wallet_address, &wasm_bytes, &args).await;
+        println!("Reputation Registry deployed at: {}", contract_address);
+
+        Self {
+            wallet_address,
+            contract_address,
+        }
+    }
+
+    /// Rates `agent_id` for the job `job_id` (1–5) via `giveFeedbackSimple`.
+    pub async fn give_feedback(
+        &self,
+        interactor: &mut Interactor,
+        job_id: &str,
+        agent_id: AgentId,
+        rating: u64,
+    ) {
+        let job_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(job_id.as_bytes());
+        let agent_id_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(&agent_id.to_be_bytes());
+        let rating_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(&rating.to_be_bytes());
+
+        interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("giveFeedbackSimple")
+            .argument(&job_buf)
+            .argument(&agent_id_buf)
+            .argument(&rating_buf)
+            .run()
+            .await;
+    }
+
+    /// The validation registry this registry reads jobs from, as set at
+    /// deploy or by `set_validation_contract_address`.
+    pub async fn get_validation_contract_address(&self) -> Address {
+        let result = vm_query(&self.contract_address, "get_validation_contract_address", &[]).await;
+        let bytes = result.first().expect("get_validation_contract_address returned nothing");
+        Address::from_slice(bytes)
+    }
+
+    pub async fn get_reputation(&self, agent_id: AgentId) -> ReputationView {
+        let args = [agent_id.to_be_bytes().to_vec()];
+        let (score, jobs) = futures::join!(
+            vm_query(&self.contract_address, "get_reputation_score", &args),
+            vm_query(&self.contract_address, "get_total_jobs", &args),
+        );
+        ReputationView {
+            average: score.first().map_or(0, |bytes| decode_top_u64(bytes)),
+            total_jobs: jobs.first().map_or(0, |bytes| decode_top_u64(bytes)),
+        }
+    }
+
+    /// Reputations of `agent_ids`, in order, from a single
+    /// [`REPUTATIONS_BATCH_VIEW`] query. Registries built without that view
+    /// fall back to concurrent [`Self::get_reputation`] calls.
+    pub async fn get_reputations_batch(&self, agent_ids: &[AgentId]) -> Vec<ReputationView> {
+        if agent_ids.is_empty() {
+            return Vec::new();
+        }
+        let args: Vec<Vec<u8>> = agent_ids.iter().map(|id| id.to_be_bytes().to_vec()).collect();
+        match try_vm_query(&self.contract_address, REPUTATIONS_BATCH_VIEW, &args).await {
+            Ok(values) => decode_reputations_batch(&values, agent_ids.len())
+                .unwrap_or_else(|e| panic!("{}", e)),
+            Err(HarnessError::QueryFailed { return_code, .. })
+                if return_code == "function not found" =>
+            {
+                futures::future::join_all(agent_ids.iter().map(|id| self.get_reputation(*id))).await
+            }
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+/// Leaderboard of every registered agent, best average first. Reputation
+/// queries for all agents are issued concurrently.
+pub async fn get_all_reputations(
+    identity: &IdentityRegistryInteractor,
+    reputation: &ReputationRegistryInteractor,
+) -> Vec<(AgentId, ReputationView)> {
+    let mut agent_ids = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = identity.get_agents(offset, AGENT_PAGE_SIZE).await;
+        agent_ids.extend(page.iter().map(|agent| agent.nonce));
+        if page.len() < AGENT_PAGE_SIZE as usize {
+            break;
+        }
+        offset += AGENT_PAGE_SIZE;
+    }
+
+    let views =
+        futures::future::join_all(agent_ids.iter().map(|id| reputation.get_reputation(*id))).await;
+    let mut leaderboard: Vec<(AgentId, ReputationView)> =
+        agent_ids.into_iter().zip(views).collect();
+    leaderboard.sort_by(|(a_id, a), (b_id, b)| b.average.cmp(&a.average).then(a_id.cmp(b_id)));
+    leaderboard
+}
@@ -0,0 +1,172 @@
+//! Multi-step scenarios replayed from JSON, so a bug report can ship as a
+//! script instead of prose. Steps run in order against a fresh registry
+//! deployed by the `deploy` step.
+//!
+//! ```json
+//! { "steps": [{ "step": "deploy" }, { "step": "register", "name": "bot", "uri": "https://..." }],
+//!   "expect": { "agent_count": 1 } }
+//! ```
+
+use multiversx_sc_snippets::imports::*;
+use serde::Deserialize;
+
+use super::{
+    bech32_to_address, generate_blocks_on_simulator, register_and_fund_owner, registered_agent,
+    send_egld, wait_for_tx, HarnessError, IdentityRegistryInteractor, NonceTracker,
+};
+
+/// Wallet that deploys and pays in every scenario; must match
+/// [`register_and_fund_owner`].
+const SCENARIO_OWNER_PEM: &str = "alice.pem";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum ScenarioStep {
+    Deploy,
+    IssueToken {
+        name: String,
+        ticker: String,
+    },
+    Register {
+        name: String,
+        uri: String,
+    },
+    /// EGLD transfer from the owner; to the registry unless `to` is a bech32.
+    Pay {
+        amount: String,
+        #[serde(default)]
+        to: Option<String>,
+    },
+    /// Looks the agent up and checks it exists (or not). `uri` is checked
+    /// against what its `register` step's event announced.
+    Query {
+        name: String,
+        #[serde(default)]
+        uri: Option<String>,
+        #[serde(default = "default_exists")]
+        exists: bool,
+    },
+}
+
+fn default_exists() -> bool {
+    true
+}
+
+/// Final-state checks run after the last step.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ScenarioExpect {
+    #[serde(default)]
+    pub agent_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ScenarioScript {
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<ScenarioStep>,
+    #[serde(default)]
+    pub expect: ScenarioExpect,
+}
+
+impl ScenarioScript {
+    pub fn load(path: &str) -> Result<Self, HarnessError> {
+        let io_err = |message: String| HarnessError::Io {
+            path: path.to_string(),
+            message,
+        };
+        let json = std::fs::read_to_string(path).map_err(|e| io_err(e.to_string()))?;
+        serde_json::from_str(&json).map_err(|e| io_err(format!("invalid scenario: {}", e)))
+    }
+
+    /// Executes every step, then the `expect` checks. Returns the registry the
+    /// scenario deployed so callers can inspect it further.
+    pub async fn run(
+        &self,
+        interactor: &mut Interactor,
+    ) -> Result<IdentityRegistryInteractor, HarnessError> {
+        let owner = register_and_fund_owner(interactor).await;
+        let payer = Wallet::from_pem_file(SCENARIO_OWNER_PEM).map_err(|e| HarnessError::Io {
+            path: SCENARIO_OWNER_PEM.to_string(),
+            message: e.to_string(),
+        })?;
+        let mut tracker = NonceTracker::new();
+        let mut identity: Option<IdentityRegistryInteractor> = None;
+        let mut uris = std::collections::BTreeMap::new();
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let fail = |message: String| HarnessError::Scenario {
+                step: index,
+                message,
+            };
+            if !matches!(step, ScenarioStep::Deploy) && identity.is_none() {
+                return Err(fail(format!("{:?} before any deploy step", step)));
+            }
+
+            match step {
+                ScenarioStep::Deploy => {
+                    identity =
+                        Some(IdentityRegistryInteractor::deploy(interactor, owner.clone()).await);
+                }
+                ScenarioStep::IssueToken { name, ticker } => {
+                    let registry = identity.as_ref().unwrap();
+                    registry.issue_token(interactor, name, ticker).await?;
+                }
+                ScenarioStep::Register { name, uri } => {
+                    let registry = identity.as_ref().unwrap();
+                    let tx_hash = registry.register_agent(interactor, name, uri).await?;
+                    let agent = registered_agent(&tx_hash).await?;
+                    uris.insert(agent.name, agent.uri);
+                }
+                ScenarioStep::Pay { amount, to } => {
+                    let registry = identity.as_ref().unwrap();
+                    let amount: u128 = amount
+                        .parse()
+                        .map_err(|_| fail(format!("amount {:?} is not an integer", amount)))?;
+                    let recipient = match to {
+                        Some(bech32) => bech32_to_address(bech32)
+                            .ok_or_else(|| fail(format!("{:?} is not a bech32 address", bech32)))?,
+                        None => registry.contract_address.clone(),
+                    };
+                    let tx_hash = send_egld(&mut tracker, &payer, &recipient, amount).await?;
+                    let status = wait_for_tx(&tx_hash, 10).await?;
+                    if status != "success" {
+                        return Err(fail(format!("payment {} ended as {}", tx_hash, status)));
+                    }
+                }
+                ScenarioStep::Query { name, uri, exists } => {
+                    let agent = identity.as_ref().unwrap().find_agent(name).await;
+                    match (&agent, exists) {
+                        (Some(_), false) => return Err(fail(format!("{} should not exist", name))),
+                        (None, true) => return Err(fail(format!("{} not found", name))),
+                        _ => {}
+                    }
+                    if let (Some(_), Some(uri)) = (&agent, uri) {
+                        let registered = uris.get(name);
+                        if registered != Some(uri) {
+                            return Err(fail(format!(
+                                "{} registered with uri {:?}, expected {:?}",
+                                name, registered, uri
+                            )));
+                        }
+                    }
+                }
+            }
+            generate_blocks_on_simulator(1).await;
+        }
+
+        let identity = identity.ok_or_else(|| HarnessError::Scenario {
+            step: self.steps.len(),
+            message: "scenario never deployed a registry".to_string(),
+        })?;
+        if let Some(expected) = self.expect.agent_count {
+            let count = identity.get_agent_count().await;
+            if count != expected {
+                return Err(HarnessError::Scenario {
+                    step: self.steps.len(),
+                    message: format!("expected {} agents, registry holds {}", expected, count),
+                });
+            }
+        }
+        Ok(identity)
+    }
+}
@@ -0,0 +1,377 @@
+//! Server-Sent Events parsing for the backend's research stream.
+
+use std::time::Duration;
+
+use super::BACKEND_URL;
+
+/// Longest gap allowed between two reads of the research stream.
+pub const DEFAULT_SSE_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Per-read timeout for the research stream; overridable through
+/// `OPENCLAW_SSE_READ_TIMEOUT_SECS`.
+pub fn sse_read_timeout() -> Duration {
+    std::env::var("OPENCLAW_SSE_READ_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SSE_READ_TIMEOUT)
+}
+
+/// One decoded `data:` payload from the research stream.
+///
+/// Accepts both the `{"token", "done", "error"}` chunk shape and the agent's
+/// native `{"type", "content"}` events (`text` → token, `complete` → done).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamChunk {
+    pub token: Option<String>,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+impl StreamChunk {
+    pub fn from_data(data: &str) -> Option<Self> {
+        let value: serde_json::Value = serde_json::from_str(data).ok()?;
+        let event_type = value["type"].as_str().unwrap_or_default();
+        let content = value["content"].as_str().map(str::to_string);
+
+        Some(Self {
+            token: value["token"]
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| if event_type == "text" { content.clone() } else { None }),
+            done: value["done"].as_bool().unwrap_or(event_type == "complete"),
+            error: value["error"]
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| if event_type == "error" { content } else { None }),
+        })
+    }
+}
+
+/// Ways a research stream can be malformed or fail to arrive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamError {
+    /// The server rejected the query.
+    Http { status: u16, body: String },
+    /// The connection failed mid-stream.
+    Transport(String),
+    /// No bytes arrived within the read timeout; `partial` holds the chunks
+    /// decoded before the stall.
+    Timeout { waited_ms: u64, partial: Vec<StreamChunk> },
+    /// The stream ended without any `done: true` chunk.
+    MissingDone,
+    /// More than one chunk claimed to be the final one.
+    MultipleDone { count: usize },
+    /// The `done` chunk at `done_index` was followed by more chunks.
+    ChunksAfterDone { done_index: usize, total: usize },
+}
+
+impl std::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Http { status, body } => write!(f, "HTTP {}: {}", status, body),
+            StreamError::Transport(message) => write!(f, "stream read failed: {}", message),
+            StreamError::Timeout { waited_ms, partial } => write!(
+                f,
+                "no data for {}ms after {} chunks",
+                waited_ms,
+                partial.len()
+            ),
+            StreamError::MissingDone => write!(f, "stream ended without a done chunk"),
+            StreamError::MultipleDone { count } => {
+                write!(f, "stream has {} done chunks, expected exactly one", count)
+            }
+            StreamError::ChunksAfterDone { done_index, total } => write!(
+                f,
+                "done at chunk {} but stream has {} chunks",
+                done_index, total
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+/// Checks the stream is well-formed: exactly one `done` chunk, and it is last.
+pub fn validate_stream(chunks: &[StreamChunk]) -> Result<(), StreamError> {
+    let done_indices: Vec<usize> = chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.done)
+        .map(|(i, _)| i)
+        .collect();
+
+    match done_indices.as_slice() {
+        [] => Err(StreamError::MissingDone),
+        [index] if *index + 1 == chunks.len() => Ok(()),
+        [index] => Err(StreamError::ChunksAfterDone {
+            done_index: *index,
+            total: chunks.len(),
+        }),
+        many => Err(StreamError::MultipleDone { count: many.len() }),
+    }
+}
+
+/// Incremental SSE framer: feed raw body bytes, get back the `data:` payload
+/// of every event completed so far. Bytes are buffered until an event is
+/// complete, so a UTF-8 character split across network chunks decodes
+/// intact. Comment lines (`: heartbeat`) are the server's keep-alives: they
+/// are counted, never returned as data.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+    heartbeats: u32,
+}
+
+impl SseParser {
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        while let Some(end) = event_end(&self.buffer) {
+            let raw_bytes: Vec<u8> = self.buffer.drain(..end).collect();
+            let raw = String::from_utf8_lossy(&raw_bytes);
+            self.heartbeats += raw.lines().filter(|line| line.starts_with(':')).count() as u32;
+            let data: Vec<&str> = raw
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect();
+            if !data.is_empty() {
+                events.push(data.join("\n"));
+            }
+        }
+        events
+    }
+
+    /// Keep-alive comments seen in completed events so far.
+    pub fn heartbeats(&self) -> u32 {
+        self.heartbeats
+    }
+}
+
+/// Length of the first complete event in `buffer`, through the blank line
+/// (`\n\n` or `\n\r\n`) that ends it.
+fn event_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| **b == b'\n')
+        .find_map(|(i, _)| match &buffer[i + 1..] {
+            [b'\n', ..] => Some(i + 2),
+            [b'\r', b'\n', ..] => Some(i + 3),
+            _ => None,
+        })
+}
+
+/// Parses a complete SSE body into chunks, stopping at the first `done`.
+pub fn parse_sse_stream(body: &str) -> Vec<StreamChunk> {
+    let mut parser = SseParser::default();
+    let mut chunks = Vec::new();
+    for data in parser.push(body.as_bytes()) {
+        if let Some(chunk) = StreamChunk::from_data(&data) {
+            let done = chunk.done;
+            chunks.push(chunk);
+            if done {
+                break;
+            }
+        }
+    }
+    chunks
+}
+
+/// Concatenates every token in the stream, in order.
+pub fn collect_tokens(chunks: &[StreamChunk]) -> String {
+    chunks.iter().filter_map(|c| c.token.as_deref()).collect()
+}
+
+/// How a query is charged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PricingModel {
+    /// A fixed amount per query, whatever the answer's length.
+    Flat(num_bigint::BigUint),
+    /// An amount per streamed token.
+    PerToken(num_bigint::BigUint),
+}
+
+/// Cost of `chunks` at `price_per_token`, counting each chunk that carries a
+/// token once, however much text it holds. It is not a tokenizer's count.
+pub fn compute_usage_cost(
+    chunks: &[StreamChunk],
+    price_per_token: num_bigint::BigUint,
+) -> num_bigint::BigUint {
+    let tokens = chunks.iter().filter(|c| c.token.is_some()).count();
+    price_per_token * num_bigint::BigUint::from(tokens)
+}
+
+/// What a stream should settle for under `pricing`: the fixed price for flat
+/// rates, otherwise [`compute_usage_cost`].
+pub fn settlement_amount(chunks: &[StreamChunk], pricing: &PricingModel) -> num_bigint::BigUint {
+    match pricing {
+        PricingModel::Flat(price) => price.clone(),
+        PricingModel::PerToken(price) => compute_usage_cost(chunks, price.clone()),
+    }
+}
+
+/// Sends a query on a paid session and reads the SSE stream until `done`.
+pub async fn backend_stream_chat(
+    session_id: &str,
+    message: &str,
+) -> Result<Vec<StreamChunk>, StreamError> {
+    stream_chat_at(BACKEND_URL, session_id, message, sse_read_timeout()).await
+}
+
+/// Outcome of one query in a research session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamStats {
+    pub query: String,
+    pub chunks: usize,
+    /// Characters of streamed content.
+    pub chars: usize,
+    /// Time from sending the query to the first streamed token, if any.
+    pub first_token: Option<Duration>,
+    /// Keep-alive comments received; they carry no content.
+    pub heartbeats: u32,
+    pub elapsed: Duration,
+}
+
+/// Sends `queries` one after another on the paid session `session_id`,
+/// returning stats per query. If the payment expires mid-session (the backend
+/// answers 402) the session ends early and only the completed queries are
+/// returned.
+pub async fn run_research_session(
+    session_id: &str,
+    queries: &[&str],
+) -> Result<Vec<StreamStats>, StreamError> {
+    run_research_session_at(BACKEND_URL, session_id, queries, sse_read_timeout()).await
+}
+
+/// [`run_research_session`] against an arbitrary base URL.
+pub async fn run_research_session_at(
+    base_url: &str,
+    session_id: &str,
+    queries: &[&str],
+    read_timeout: Duration,
+) -> Result<Vec<StreamStats>, StreamError> {
+    let mut stats = Vec::with_capacity(queries.len());
+    for query in queries {
+        let started = std::time::Instant::now();
+        let stream = match stream_chat_timed_at(base_url, session_id, query, read_timeout).await {
+            Ok(streamed) => streamed,
+            Err(StreamError::Http { status: 402, .. }) => {
+                println!(
+                    "⚠️ Payment for session {} expired after {} of {} queries",
+                    session_id,
+                    stats.len(),
+                    queries.len()
+                );
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        stats.push(StreamStats {
+            query: query.to_string(),
+            chunks: stream.chunks.len(),
+            chars: collect_tokens(&stream.chunks).chars().count(),
+            first_token: stream.first_token,
+            heartbeats: stream.heartbeats,
+            elapsed: started.elapsed(),
+        });
+    }
+    Ok(stats)
+}
+
+/// Like [`backend_stream_chat`] against an arbitrary base URL, failing with
+/// [`StreamError::Timeout`] if any single read stalls past `read_timeout`.
+pub async fn stream_chat_at(
+    base_url: &str,
+    session_id: &str,
+    message: &str,
+    read_timeout: Duration,
+) -> Result<Vec<StreamChunk>, StreamError> {
+    stream_chat_timed_at(base_url, session_id, message, read_timeout)
+        .await
+        .map(|stream| stream.chunks)
+}
+
+/// A research stream read through its `done` chunk (or to EOF).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimedStream {
+    pub chunks: Vec<StreamChunk>,
+    /// Time from sending the query to the first token, if any arrived.
+    pub first_token: Option<Duration>,
+    /// Keep-alive comments interleaved with the data.
+    pub heartbeats: u32,
+}
+
+/// [`stream_chat_at`], also reporting how long after sending the query the
+/// first token arrived and how many heartbeats the server sent.
+pub async fn stream_chat_timed_at(
+    base_url: &str,
+    session_id: &str,
+    message: &str,
+    read_timeout: Duration,
+) -> Result<TimedStream, StreamError> {
+    let started = std::time::Instant::now();
+    let mut first_token = None;
+    let client = reqwest::Client::new();
+    let request = client
+        .post(format!("{}/api/chat", base_url))
+        .json(&serde_json::json!({
+            "message": message,
+            "sessionId": session_id
+        }))
+        .send();
+    let mut resp = match tokio::time::timeout(read_timeout, request).await {
+        Err(_) => {
+            return Err(StreamError::Timeout {
+                waited_ms: read_timeout.as_millis() as u64,
+                partial: Vec::new(),
+            })
+        }
+        Ok(result) => result.map_err(|e| StreamError::Transport(e.to_string()))?,
+    };
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(StreamError::Http { status, body });
+    }
+
+    let mut parser = SseParser::default();
+    let mut chunks = Vec::new();
+    loop {
+        let bytes = match tokio::time::timeout(read_timeout, resp.chunk()).await {
+            Err(_) => {
+                return Err(StreamError::Timeout {
+                    waited_ms: read_timeout.as_millis() as u64,
+                    partial: chunks,
+                })
+            }
+            Ok(Err(e)) => return Err(StreamError::Transport(e.to_string())),
+            Ok(Ok(None)) => {
+                return Ok(TimedStream {
+                    chunks,
+                    first_token,
+                    heartbeats: parser.heartbeats(),
+                })
+            }
+            Ok(Ok(Some(bytes))) => bytes,
+        };
+        for data in parser.push(&bytes) {
+            if let Some(chunk) = StreamChunk::from_data(&data) {
+                if first_token.is_none() && chunk.token.is_some() {
+                    first_token = Some(started.elapsed());
+                }
+                let done = chunk.done;
+                chunks.push(chunk);
+                if done {
+                    return Ok(TimedStream {
+                        chunks,
+                        first_token,
+                        heartbeats: parser.heartbeats(),
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,760 @@
+//! Transactions signed locally and submitted straight to the gateway.
+//!
+//! The interactor waits for each tx to complete before returning, which hides
+//! nonce handling entirely. These helpers send without waiting, so several txs
+//! from one wallet can be in flight — nonces come from a [`NonceTracker`].
+
+use std::time::Duration;
+
+use multiversx_sc::types::Address;
+use multiversx_sc_snippets::imports::*;
+use multiversx_sc_snippets::sdk::data::transaction::Transaction;
+
+use super::{
+    address_to_bech32, bech32_to_address, generate_blocks_on_simulator, get_account_balance,
+    get_account_nonce, get_current_block, get_simulator_chain_id, poll_until, read_json,
+    submit_with_retry, HarnessError, NonceTracker, GATEWAY_URL,
+};
+
+pub const DEFAULT_GAS_PRICE: u64 = 1_000_000_000;
+pub const TRANSFER_GAS_LIMIT: u64 = 50_000;
+/// Covers an `ESDTTransfer` to a wallet, data field included.
+pub const ESDT_TRANSFER_GAS_LIMIT: u64 = 500_000;
+/// Gas used for contract calls when the cost endpoint can't produce an estimate.
+pub const FALLBACK_CALL_GAS_LIMIT: u64 = 600_000_000;
+/// Gas for [`simulate_failing_payment`]: enough to reach the contract and
+/// have it reject the call.
+pub const REVERTING_CALL_GAS_LIMIT: u64 = 10_000_000;
+
+/// Pays `recipient` in EGLD from `payer` and waits for the transfer to be
+/// processed, returning its hash. Point `recipient` somewhere other than the
+/// agent to exercise the backend's receiver check.
+pub async fn simulate_payment(
+    tracker: &mut NonceTracker,
+    payer: &Wallet,
+    recipient: &Address,
+    amount: u128,
+) -> Result<String, HarnessError> {
+    let tx_hash = send_egld(tracker, payer, recipient, amount).await?;
+    let status = wait_for_tx(&tx_hash, 10).await?;
+    if status != "success" {
+        return Err(HarnessError::Gateway {
+            message: format!("payment {} ended as {}", tx_hash, status),
+        });
+    }
+    Ok(tx_hash)
+}
+
+/// Pays `recipient` `amount` base units of the ESDT `token_id` from `payer`
+/// and waits for the transfer to be processed, returning its hash.
+pub async fn simulate_esdt_payment(
+    tracker: &mut NonceTracker,
+    payer: &Wallet,
+    recipient: &Address,
+    token_id: &str,
+    amount: &num_bigint::BigUint,
+) -> Result<String, HarnessError> {
+    use base64::Engine;
+
+    let sender = payer.to_address();
+    let nonce = tracker.next(&sender).await?;
+    let mut unsigned = build_unsigned_payment_tx(&sender, recipient, 0, nonce).await;
+    let data = esdt_transfer_data(token_id, amount);
+    unsigned["data"] = base64::engine::general_purpose::STANDARD.encode(data).into();
+    unsigned["gasLimit"] = ESDT_TRANSFER_GAS_LIMIT.into();
+
+    let tx_hash = sign_and_submit(payer, unsigned).await?;
+    let status = wait_for_tx(&tx_hash, 10).await?;
+    if status != "success" {
+        return Err(HarnessError::Gateway {
+            message: format!("payment {} ended as {}", tx_hash, status),
+        });
+    }
+    Ok(tx_hash)
+}
+
+/// Sends `amount` EGLD to `endpoint` on the contract `recipient` and requires
+/// the tx to fail on-chain. Point it at a non-payable endpoint (a view, say)
+/// to get a payment-shaped tx that reverts and moves no funds.
+pub async fn simulate_failing_payment(
+    tracker: &mut NonceTracker,
+    payer: &Wallet,
+    recipient: &Address,
+    endpoint: &str,
+    amount: u128,
+) -> Result<String, HarnessError> {
+    use base64::Engine;
+
+    let sender = payer.to_address();
+    let nonce = tracker.next(&sender).await?;
+    let mut unsigned = build_unsigned_payment_tx(&sender, recipient, amount, nonce).await;
+    unsigned["data"] = base64::engine::general_purpose::STANDARD.encode(endpoint).into();
+    unsigned["gasLimit"] = REVERTING_CALL_GAS_LIMIT.into();
+
+    let tx_hash = sign_and_submit(payer, unsigned).await?;
+    let status = wait_for_tx(&tx_hash, 10).await?;
+    if status != "fail" {
+        return Err(HarnessError::Gateway {
+            message: format!(
+                "payment {} to {} was meant to revert but ended as {}",
+                tx_hash, endpoint, status
+            ),
+        });
+    }
+    Ok(tx_hash)
+}
+
+/// Sends an ESDT transfer of `amount` `token_id` to `recipient` that `payer`
+/// can't cover, and requires the tx to fail on-chain. Unlike
+/// [`simulate_failing_payment`] this works when `recipient` is a wallet: the
+/// transfer fails in the payer's own shard and moves nothing.
+pub async fn simulate_failing_esdt_payment(
+    tracker: &mut NonceTracker,
+    payer: &Wallet,
+    recipient: &Address,
+    token_id: &str,
+    amount: &num_bigint::BigUint,
+) -> Result<String, HarnessError> {
+    use base64::Engine;
+
+    let sender = payer.to_address();
+    let nonce = tracker.next(&sender).await?;
+    let mut unsigned = build_unsigned_payment_tx(&sender, recipient, 0, nonce).await;
+    let data = esdt_transfer_data(token_id, amount);
+    unsigned["data"] = base64::engine::general_purpose::STANDARD.encode(data).into();
+    unsigned["gasLimit"] = ESDT_TRANSFER_GAS_LIMIT.into();
+
+    let tx_hash = sign_and_submit(payer, unsigned).await?;
+    let status = wait_for_tx(&tx_hash, 10).await?;
+    if status != "fail" {
+        return Err(HarnessError::Gateway {
+            message: format!(
+                "{} transfer {} was meant to fail but ended as {}",
+                token_id, tx_hash, status
+            ),
+        });
+    }
+    Ok(tx_hash)
+}
+
+fn esdt_transfer_data(token_id: &str, amount: &num_bigint::BigUint) -> String {
+    format!(
+        "ESDTTransfer@{}@{}",
+        hex::encode(token_id),
+        hex::encode(amount.to_bytes_be())
+    )
+}
+
+/// Signs and submits a plain EGLD transfer, returning the tx hash without
+/// waiting for it to be processed.
+pub async fn send_egld(
+    tracker: &mut NonceTracker,
+    wallet: &Wallet,
+    to: &Address,
+    amount: u128,
+) -> Result<String, HarnessError> {
+    let sender = wallet.to_address();
+    let nonce = tracker.next(&sender).await?;
+    let unsigned = build_unsigned_payment_tx(&sender, to, amount, nonce).await;
+    sign_and_submit(wallet, unsigned).await
+}
+
+/// Gateway JSON for an EGLD transfer, ready to be signed: everything a wallet
+/// needs except the signature.
+pub async fn build_unsigned_payment_tx(
+    sender: &Address,
+    to: &Address,
+    amount: u128,
+    nonce: u64,
+) -> serde_json::Value {
+    serde_json::json!({
+        "nonce": nonce,
+        "value": amount.to_string(),
+        "receiver": address_to_bech32(to),
+        "sender": address_to_bech32(sender),
+        "gasPrice": DEFAULT_GAS_PRICE,
+        "gasLimit": TRANSFER_GAS_LIMIT,
+        "chainID": get_simulator_chain_id().await,
+        "version": 1,
+    })
+}
+
+/// Signs `unsigned` with `wallet` and posts it to `/transaction/send`,
+/// returning the hash. Balance and nonce rejections come back as
+/// [`HarnessError::InsufficientFunds`] and [`HarnessError::NonceMismatch`].
+pub async fn sign_and_submit(
+    wallet: &Wallet,
+    unsigned: serde_json::Value,
+) -> Result<String, HarnessError> {
+    sign_and_submit_at(GATEWAY_URL, wallet, unsigned).await
+}
+
+/// [`sign_and_submit`] through the gateway at `gateway_url`. Transient 5xx
+/// answers are retried via [`submit_with_retry`].
+pub async fn sign_and_submit_at(
+    gateway_url: &str,
+    wallet: &Wallet,
+    unsigned: serde_json::Value,
+) -> Result<String, HarnessError> {
+    let mut tx: Transaction = serde_json::from_value(unsigned).map_err(|e| HarnessError::Gateway {
+        message: format!("failed to build tx: {}", e),
+    })?;
+    tx.signature = Some(hex::encode(wallet.sign_tx(&tx)));
+
+    let client = reqwest::Client::new();
+    let url = format!("{}/transaction/send", gateway_url);
+    let resp = submit_with_retry(|| client.post(&url).json(&tx)).await?;
+    let resp = read_json(resp).await?;
+
+    if let Some(hash) = resp["data"]["txHash"].as_str() {
+        return Ok(hash.to_string());
+    }
+    let error = resp["error"].as_str().unwrap_or_default().to_string();
+    if error.to_lowercase().contains("insufficient funds") {
+        let value: num_bigint::BigUint = tx.value.parse().unwrap_or_default();
+        let max_fee = num_bigint::BigUint::from(tx.gas_price) * tx.gas_limit;
+        return Err(HarnessError::InsufficientFunds {
+            required: value + max_fee,
+            available: get_account_balance(&wallet.to_address()).await?,
+        });
+    }
+    if error.to_lowercase().contains("nonce") {
+        return Err(HarnessError::NonceMismatch {
+            tx_nonce: tx.nonce,
+            message: error,
+        });
+    }
+    Err(HarnessError::Gateway {
+        message: format!("transaction/send rejected tx nonce {}: {}", tx.nonce, resp["error"]),
+    })
+}
+
+/// [`send_egld`], but a nonce rejection resyncs the sender's nonce from the
+/// gateway and resends once.
+pub async fn send_egld_with_retry(
+    tracker: &mut NonceTracker,
+    wallet: &Wallet,
+    to: &Address,
+    amount: u128,
+) -> Result<String, HarnessError> {
+    match send_egld(tracker, wallet, to, amount).await {
+        Err(err @ HarnessError::NonceMismatch { .. }) => {
+            let nonce = tracker.sync(&wallet.to_address()).await?;
+            println!("⚠️ {} — resynced nonce to {}, retrying", err, nonce);
+            send_egld(tracker, wallet, to, amount).await
+        }
+        result => result,
+    }
+}
+
+/// Asks the gateway's `/transaction/cost` endpoint how much gas a contract
+/// call would consume, without submitting anything.
+pub async fn estimate_gas(
+    from: &Address,
+    to: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+) -> Result<u64, HarnessError> {
+    use base64::Engine;
+
+    let data = std::iter::once(endpoint.to_string())
+        .chain(args.iter().map(hex::encode))
+        .collect::<Vec<_>>()
+        .join("@");
+    let body = serde_json::json!({
+        "nonce": get_account_nonce(from).await?,
+        "value": "0",
+        "receiver": address_to_bech32(to),
+        "sender": address_to_bech32(from),
+        "gasPrice": DEFAULT_GAS_PRICE,
+        "data": base64::engine::general_purpose::STANDARD.encode(data),
+        "chainID": get_simulator_chain_id().await,
+        "version": 1,
+    });
+
+    let client = reqwest::Client::new();
+    let resp = client
+        .post(format!("{}/transaction/cost", GATEWAY_URL))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    let resp = read_json(resp).await?;
+
+    match resp["data"]["txGasUnits"].as_u64() {
+        Some(units) if units > 0 => Ok(units),
+        _ => Err(HarnessError::Gateway {
+            message: format!(
+                "gas estimate for {} failed: {} {}",
+                endpoint, resp["data"]["returnMessage"], resp["error"]
+            ),
+        }),
+    }
+}
+
+/// Calls `endpoint` with gas set from [`estimate_gas`] plus a 20% buffer,
+/// falling back to [`FALLBACK_CALL_GAS_LIMIT`] if estimation fails.
+pub async fn call_endpoint(
+    interactor: &mut Interactor,
+    from: &Address,
+    to: &Address,
+    endpoint: &str,
+    args: &[Vec<u8>],
+) {
+    let gas = match estimate_gas(from, to, endpoint, args).await {
+        Ok(estimate) => estimate + estimate / 5,
+        Err(e) => {
+            println!("⚠️ {} — using {} gas", e, FALLBACK_CALL_GAS_LIMIT);
+            FALLBACK_CALL_GAS_LIMIT
+        }
+    };
+
+    let mut call = interactor.tx().from(from).to(to).gas(gas).raw_call(endpoint);
+    for arg in args {
+        let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+        call = call.argument(&arg_buf);
+    }
+    call.run().await;
+}
+
+/// ESDT-style identifier of a token, e.g. `USDC-350c4e`.
+pub type TokenId = String;
+
+/// Identifier that stands for native EGLD inside a multi-transfer.
+pub const EGLD_TOKEN_ID: &str = "EGLD-000000";
+
+/// Sends several fungible tokens (EGLD via [`EGLD_TOKEN_ID`]) from `from` to
+/// `to` in one `MultiESDTNFTTransfer`, optionally calling `endpoint` (with
+/// no arguments) on the receiving contract. Returns the tx hash.
+pub async fn multi_transfer(
+    interactor: &mut Interactor,
+    from: &Address,
+    to: &Address,
+    transfers: &[(TokenId, num_bigint::BigUint)],
+    endpoint: Option<&str>,
+) -> Result<String, HarnessError> {
+    // Built-in function: addressed to the sender, receiver goes first
+    let mut args = vec![
+        to.as_bytes().to_vec(),
+        (transfers.len() as u32).to_be_bytes().to_vec(),
+    ];
+    for (token_id, amount) in transfers {
+        args.push(token_id.as_bytes().to_vec());
+        args.push(Vec::new());
+        args.push(amount.to_bytes_be());
+    }
+    if let Some(endpoint) = endpoint {
+        args.push(endpoint.as_bytes().to_vec());
+    }
+
+    let mut call = interactor
+        .tx()
+        .from(from)
+        .to(from)
+        .gas(FALLBACK_CALL_GAS_LIMIT)
+        .raw_call("MultiESDTNFTTransfer");
+    for arg in &args {
+        let arg_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(arg);
+        call = call.argument(&arg_buf);
+    }
+    call.returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+        .run()
+        .await
+        .map(|tx_hash| hex::encode(tx_hash.as_bytes()))
+        .map_err(|err| HarnessError::ContractRevert {
+            status: err.status,
+            message: err.message,
+        })
+}
+
+/// Returns the processing status the gateway reports for `tx_hash`
+/// (`pending`, `success`, `fail`, ...).
+pub async fn get_tx_status(tx_hash: &str) -> Result<String, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/transaction/{}/status", GATEWAY_URL, tx_hash))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    let resp = read_json(resp).await?;
+
+    resp["data"]["status"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("tx status missing for {}: {}", tx_hash, resp),
+        })
+}
+
+/// Full transaction object (`data.transaction`) for `tx_hash`, including
+/// smart contract results and logs.
+pub async fn get_transaction(tx_hash: &str) -> Result<serde_json::Value, HarnessError> {
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/transaction/{}?withResults=true", GATEWAY_URL, tx_hash))
+        .send()
+        .await
+        .map_err(|e| HarnessError::Gateway {
+            message: e.to_string(),
+        })?;
+    let resp = read_json(resp).await?;
+
+    let tx = &resp["data"]["transaction"];
+    if tx.is_null() {
+        return Err(HarnessError::Gateway {
+            message: format!("transaction {} not found: {}", tx_hash, resp["error"]),
+        });
+    }
+    Ok(tx.clone())
+}
+
+/// Gas `tx_hash` actually consumed (`gasUsed`); the rest of its gas limit
+/// was refunded to the sender.
+pub async fn get_tx_gas_used(tx_hash: &str) -> Result<u64, HarnessError> {
+    let tx = get_transaction(tx_hash).await?;
+    tx["gasUsed"].as_u64().ok_or_else(|| HarnessError::Gateway {
+        message: format!("tx {} has no gasUsed yet", tx_hash),
+    })
+}
+
+/// Fee the sender paid for `tx_hash`, in denominated EGLD, as reported in the
+/// processed tx's `fee` field.
+pub async fn get_tx_fee(tx_hash: &str) -> Result<num_bigint::BigUint, HarnessError> {
+    let tx = get_transaction(tx_hash).await?;
+    tx["fee"]
+        .as_str()
+        .and_then(|fee| fee.parse().ok())
+        .ok_or_else(|| HarnessError::Gateway {
+            message: format!("tx {} has no fee yet: {}", tx_hash, tx["fee"]),
+        })
+}
+
+/// Nonce the processed tx `tx_hash` was sent with.
+pub async fn get_tx_nonce(tx_hash: &str) -> Result<u64, HarnessError> {
+    let tx = get_transaction(tx_hash).await?;
+    tx["nonce"].as_u64().ok_or_else(|| HarnessError::Gateway {
+        message: format!("tx {} has no nonce: {}", tx_hash, tx["nonce"]),
+    })
+}
+
+/// Every event logged while processing `tx`: the tx's own log first, then
+/// each smart contract result's, in gateway order.
+pub fn tx_events(tx: &serde_json::Value) -> Vec<serde_json::Value> {
+    let results = tx["smartContractResults"].as_array().cloned().unwrap_or_default();
+    std::iter::once(&tx["logs"])
+        .chain(results.iter().map(|scr| &scr["logs"]))
+        .filter_map(|logs| logs["events"].as_array())
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// An event's topics, base64-decoded; undecodable topics come back empty.
+pub fn event_topics(event: &serde_json::Value) -> Vec<Vec<u8>> {
+    use base64::Engine;
+
+    event["topics"]
+        .as_array()
+        .map(|topics| {
+            topics
+                .iter()
+                .map(|topic| {
+                    base64::engine::general_purpose::STANDARD
+                        .decode(topic.as_str().unwrap_or_default())
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// An event's `data` payload, base64-decoded; empty when absent.
+pub fn event_data(event: &serde_json::Value) -> Vec<u8> {
+    use base64::Engine;
+
+    base64::engine::general_purpose::STANDARD
+        .decode(event["data"].as_str().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Identifiers of the events in [`tx_events`], in the same order.
+pub fn tx_event_identifiers(tx: &serde_json::Value) -> Vec<String> {
+    tx_events(tx)
+        .iter()
+        .filter_map(|event| event["identifier"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// Checks `expected` occurs within `events` in the given order; unrelated
+/// events in between are allowed. Names the first missing or misplaced one.
+pub fn check_event_order(events: &[String], expected: &[&str]) -> Result<(), String> {
+    let mut position = 0;
+    for identifier in expected {
+        match events[position..].iter().position(|e| e == identifier) {
+            Some(offset) => position += offset + 1,
+            None if events.iter().any(|e| e == identifier) => {
+                return Err(format!("{} is out of order in {:?}", identifier, events));
+            }
+            None => return Err(format!("{} is missing from {:?}", identifier, events)),
+        }
+    }
+    Ok(())
+}
+
+/// Asserts the events in `identifiers` were emitted by `tx_hash` in that order.
+pub async fn assert_event_order(tx_hash: &str, identifiers: &[&str]) {
+    let tx = get_transaction(tx_hash)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to fetch tx {}: {}", tx_hash, e));
+    let events = tx_event_identifiers(&tx);
+    if let Err(e) = check_event_order(&events, identifiers) {
+        panic!("Unexpected event order in tx {}: {}", tx_hash, e);
+    }
+}
+
+/// Decoded `data` field of a smart contract result. The gateway returns SCR
+/// data as plain `endpoint@args` text, but some versions base64 it.
+fn scr_data(scr: &serde_json::Value) -> String {
+    use base64::Engine;
+
+    let raw = scr["data"].as_str().unwrap_or_default();
+    if raw.contains('@') {
+        return raw.to_string();
+    }
+    base64::engine::general_purpose::STANDARD
+        .decode(raw)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_else(|| raw.to_string())
+}
+
+/// Asserts that, while processing `tx_hash`, `from_contract` called
+/// `endpoint` on `to_contract` — proven by a matching smart contract result.
+pub async fn assert_cross_contract_call(
+    tx_hash: &str,
+    from_contract: &Address,
+    to_contract: &Address,
+    endpoint: &str,
+) {
+    let tx = get_transaction(tx_hash)
+        .await
+        .unwrap_or_else(|e| panic!("Failed to fetch tx {}: {}", tx_hash, e));
+    let from = address_to_bech32(from_contract);
+    let to = address_to_bech32(to_contract);
+
+    let scrs = tx["smartContractResults"].as_array().cloned().unwrap_or_default();
+    let found = scrs.iter().any(|scr| {
+        scr["sender"] == from.as_str()
+            && scr["receiver"] == to.as_str()
+            && scr_data(scr).split('@').next() == Some(endpoint)
+    });
+
+    let seen: Vec<String> = scrs
+        .iter()
+        .map(|scr| format!("{} -> {}: {}", scr["sender"], scr["receiver"], scr_data(scr)))
+        .collect();
+    assert!(
+        found,
+        "tx {}: no call {} -> {}::{} among SC results:\n{}",
+        tx_hash,
+        from,
+        to,
+        endpoint,
+        seen.join("\n")
+    );
+}
+
+/// Minimum gas a tx needs just to be accepted: base cost plus a per-byte
+/// charge on its data field.
+pub const MIN_GAS_LIMIT: u64 = 50_000;
+pub const GAS_PER_DATA_BYTE: u64 = 1_500;
+
+/// Why a tx has not left the pending state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingReason {
+    /// The tx nonce is ahead of the account nonce; it waits for the gap to fill.
+    NonceGap { tx_nonce: u64, account_nonce: u64 },
+    /// The gas limit doesn't cover the tx's own data cost.
+    InsufficientGas { gas_limit: u64, required: u64 },
+    /// The simulator is not producing blocks, so nothing gets processed.
+    ChainStalled,
+    /// None of the known causes apply (or the gateway no longer knows the tx).
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingDiagnosis {
+    pub reason: PendingReason,
+    pub suggested_action: String,
+}
+
+/// Works out why `tx_hash` is stuck: nonce gap, gas below the data cost, or a
+/// simulator that has stopped producing blocks. `since_height` is the block
+/// height read when the wait began; a chain still at it has stalled. Only
+/// reads the chain: generating a block here could process the very tx being
+/// diagnosed.
+pub async fn diagnose_pending_tx(tx_hash: &str, since_height: u64) -> PendingDiagnosis {
+    use base64::Engine;
+
+    match get_current_block().await {
+        Ok(block) if block.nonce <= since_height => {
+            return PendingDiagnosis {
+                reason: PendingReason::ChainStalled,
+                suggested_action: format!(
+                    "restart the chain simulator (height stuck at {})",
+                    block.nonce
+                ),
+            };
+        }
+        Ok(_) => {}
+        Err(e) => {
+            return PendingDiagnosis {
+                reason: PendingReason::Unknown(e.to_string()),
+                suggested_action: "check the gateway is reachable".to_string(),
+            }
+        }
+    }
+
+    let tx = match get_transaction(tx_hash).await {
+        Ok(tx) => tx,
+        Err(e) => {
+            return PendingDiagnosis {
+                reason: PendingReason::Unknown(e.to_string()),
+                suggested_action: "check the tx was accepted by /transaction/send".to_string(),
+            }
+        }
+    };
+
+    let tx_nonce = tx["nonce"].as_u64().unwrap_or_default();
+    let sender = tx["sender"].as_str().unwrap_or_default();
+    let account_nonce = match bech32_to_address(sender) {
+        Some(address) => get_account_nonce(&address).await.ok(),
+        None => None,
+    };
+    if let Some(account_nonce) = account_nonce {
+        if tx_nonce > account_nonce {
+            return PendingDiagnosis {
+                reason: PendingReason::NonceGap {
+                    tx_nonce,
+                    account_nonce,
+                },
+                suggested_action: format!(
+                    "send the missing nonces {}..{} or resync the NonceTracker",
+                    account_nonce, tx_nonce
+                ),
+            };
+        }
+    }
+
+    let data_len = tx["data"]
+        .as_str()
+        .and_then(|d| base64::engine::general_purpose::STANDARD.decode(d).ok())
+        .map_or(0, |d| d.len() as u64);
+    let required = MIN_GAS_LIMIT + GAS_PER_DATA_BYTE * data_len;
+    let gas_limit = tx["gasLimit"].as_u64().unwrap_or_default();
+    if gas_limit < required {
+        return PendingDiagnosis {
+            reason: PendingReason::InsufficientGas {
+                gas_limit,
+                required,
+            },
+            suggested_action: format!("raise the gas limit to at least {}", required),
+        };
+    }
+
+    PendingDiagnosis {
+        reason: PendingReason::Unknown(format!("status {}", tx["status"])),
+        suggested_action: "inspect the tx on the gateway".to_string(),
+    }
+}
+
+/// `callType` of the smart contract result that runs an async callback.
+const ASYNC_CALLBACK_CALL_TYPE: u64 = 2;
+
+/// The smart contract result among `tx`'s that carries its async callback,
+/// if it has arrived.
+pub fn find_callback_result(tx: &serde_json::Value) -> Option<serde_json::Value> {
+    tx["smartContractResults"]
+        .as_array()?
+        .iter()
+        .find(|scr| {
+            scr["callType"].as_u64() == Some(ASYNC_CALLBACK_CALL_TYPE)
+                || scr["callType"] == "asynchronousCallBack"
+        })
+        .cloned()
+}
+
+/// Generates blocks until the async call made by `tx_hash` has come back,
+/// returning the callback's smart contract result. The callback runs blocks
+/// after the original call, so state it writes (e.g. an issued token id) is
+/// only readable once this returns.
+pub async fn wait_for_callback(
+    tx_hash: &str,
+    max_blocks: u32,
+) -> Result<serde_json::Value, HarnessError> {
+    let started = std::time::Instant::now();
+    let mut blocks = 0;
+    let (callback, _) = poll_until(
+        &format!("callback of tx {}", tx_hash),
+        || {
+            blocks += 1;
+            let generated = blocks;
+            async move {
+                generate_blocks_on_simulator(1).await;
+                let tx = get_transaction(tx_hash).await?;
+                Ok((find_callback_result(&tx), generated))
+            }
+        },
+        |(callback, generated): &(Option<serde_json::Value>, u32)| {
+            callback.is_some() || *generated >= max_blocks
+        },
+        Duration::ZERO,
+        TX_WAIT_TIMEOUT,
+    )
+    .await?;
+
+    callback.ok_or_else(|| HarnessError::Timeout {
+        what: format!("callback of tx {} within {} blocks", tx_hash, max_blocks),
+        waited_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Wall-clock backstop for [`wait_for_tx`], which is otherwise bounded by
+/// the number of blocks it generates.
+const TX_WAIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Generates blocks until `tx_hash` leaves the pending state, returning its
+/// final status. A tx that never leaves it is reported with a diagnosis.
+pub async fn wait_for_tx(tx_hash: &str, max_blocks: u32) -> Result<String, HarnessError> {
+    let since_height = get_current_block().await?.nonce;
+    let mut blocks = 0;
+    let (status, _) = poll_until(
+        &format!("tx {}", tx_hash),
+        || {
+            blocks += 1;
+            let generated = blocks;
+            async move {
+                generate_blocks_on_simulator(1).await;
+                Ok((get_tx_status(tx_hash).await?, generated))
+            }
+        },
+        |(status, generated): &(String, u32)| {
+            (status != "pending" && status != "received") || *generated >= max_blocks
+        },
+        Duration::ZERO,
+        TX_WAIT_TIMEOUT,
+    )
+    .await?;
+
+    if status == "pending" || status == "received" {
+        return Err(HarnessError::TxPending {
+            tx_hash: tx_hash.to_string(),
+            diagnosis: diagnose_pending_tx(tx_hash, since_height).await,
+        });
+    }
+    Ok(status)
+}
@@ -0,0 +1,291 @@
+//! Validation Registry interactor.
+
+use multiversx_sc::types::{Address, ManagedBuffer};
+use multiversx_sc_snippets::imports::*;
+
+use super::{
+    check_wasm_size, deploy_with_args, resolve_artifact, vm_query, AgentId, HarnessError,
+    NestedDecoder, VALIDATION_WASM,
+};
+
+/// Highest response `validation_response` accepts; responses run from 0 to
+/// this inclusive and anything above reverts.
+pub const MAX_VALIDATION_SCORE: u8 = 100;
+
+/// Keccak-256 of `request_uri`, used as the `request_hash` identifying a
+/// validation request.
+pub fn request_hash(request_uri: &str) -> Vec<u8> {
+    use sha3::{Digest, Keccak256};
+
+    Keccak256::digest(request_uri.as_bytes()).to_vec()
+}
+
+/// A validation request as stored by the registry (`ValidationRequestData`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationStatus {
+    pub validator_address: Address,
+    pub agent_nonce: AgentId,
+    pub job_id: Vec<u8>,
+    /// Latest response from the validator; 0 until it answers.
+    pub response: u8,
+    pub response_hash: Vec<u8>,
+    pub tag: Vec<u8>,
+    pub last_update: u64,
+}
+
+impl ValidationStatus {
+    pub fn decode(bytes: &[u8]) -> Result<Self, HarnessError> {
+        let mut decoder = NestedDecoder::new(bytes);
+        Ok(Self {
+            validator_address: Address::from_slice(decoder.read_bytes(32)?),
+            agent_nonce: decoder.read_u64()?,
+            job_id: decoder.read_buffer()?,
+            response: decoder.read_u8()?,
+            response_hash: decoder.read_buffer()?,
+            tag: decoder.read_buffer()?,
+            last_update: decoder.read_u64()?,
+        })
+    }
+}
+
+/// Jobs are opened with `init_job`; the agent owner asks a validator to
+/// check one with `validation_request`, and the validator answers with a
+/// response from 0 to [`MAX_VALIDATION_SCORE`] via `validation_response`.
+pub struct ValidationRegistryInteractor {
+    pub wallet_address: Address,
+    pub contract_address: Address,
+}
+
+impl ValidationRegistryInteractor {
+    /// Deploys the registry bound to `identity_address`, which it uses to
+    /// check that validated agents exist.
+    pub async fn deploy(
+        interactor: &mut Interactor,
+        wallet_address: Address,
+        identity_address: &Address,
+    ) -> Self {
+        println!("Deploying Validation Registry...");
+        let wasm_path = resolve_artifact(VALIDATION_WASM).unwrap_or_else(|e| panic!("{}", e));
+        if let Err(e) = check_wasm_size(&wasm_path) {
+            println!("⚠️ {}", e);
+        }
+        let wasm_bytes = std::fs::read(&wasm_path)
+            .expect("Failed to read validation WASM. Run setup.sh first.");
+
+        let args = [identity_address.as_bytes().to_vec()];
+        let contract_address = deploy_with_args(interactor, &wallet_address, &wasm_bytes, &args).await;
+        println!("Validation Registry deployed at: {}", contract_address);
+
+        Self {
+            wallet_address,
+            contract_address,
+        }
+    }
+
+    /// Opens `job_id` for `agent_nonce` with the deployer as employer,
+    /// without a service (so no payment is attached).
+    pub async fn init_job(
+        &self,
+        interactor: &mut Interactor,
+        job_id: &str,
+        agent_nonce: AgentId,
+    ) -> Result<String, HarnessError> {
+        let job_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(job_id.as_bytes());
+        let nonce_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(&agent_nonce.to_be_bytes());
+
+        interactor
+            .tx()
+            .from(&self.wallet_address)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("init_job")
+            .argument(&job_buf)
+            .argument(&nonce_buf)
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map(|tx_hash| hex::encode(tx_hash.as_bytes()))
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })
+    }
+
+    /// Opens `job_id` for service `service_id` of agent `agent_nonce`, with
+    /// `employer` paying `amount` EGLD. The registry rejects payments below
+    /// the service price and does not refund overpayment: the whole payment,
+    /// excess included, goes to the agent's owner.
+    pub async fn init_paid_job(
+        &self,
+        interactor: &mut Interactor,
+        employer: &Address,
+        job_id: &str,
+        agent_nonce: AgentId,
+        service_id: u32,
+        amount: u64,
+    ) -> Result<String, HarnessError> {
+        let job_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(job_id.as_bytes());
+        let nonce_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(&agent_nonce.to_be_bytes());
+        let service_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(&service_id.to_be_bytes());
+
+        interactor
+            .tx()
+            .from(employer)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .egld(amount)
+            .raw_call("init_job")
+            .argument(&job_buf)
+            .argument(&nonce_buf)
+            .argument(&service_buf)
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map(|tx_hash| hex::encode(tx_hash.as_bytes()))
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })
+    }
+
+    /// Asks `validator` to check `job_id`, identified from then on by
+    /// `request_hash`. Sent from `owner`, which must own the job's agent.
+    pub async fn validation_request(
+        &self,
+        interactor: &mut Interactor,
+        owner: &Address,
+        job_id: &str,
+        validator: &Address,
+        request_uri: &str,
+        request_hash: &[u8],
+    ) -> Result<String, HarnessError> {
+        let job_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(job_id.as_bytes());
+        let validator_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(validator.as_bytes());
+        let uri_buf: ManagedBuffer<StaticApi> =
+            ManagedBuffer::new_from_bytes(request_uri.as_bytes());
+        let hash_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(request_hash);
+
+        interactor
+            .tx()
+            .from(owner)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("validation_request")
+            .argument(&job_buf)
+            .argument(&validator_buf)
+            .argument(&uri_buf)
+            .argument(&hash_buf)
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map(|tx_hash| hex::encode(tx_hash.as_bytes()))
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })
+    }
+
+    /// Opens `job_id` for `agent_nonce` and asks `validator` to check it,
+    /// returning the request hash. The deployer must own the agent.
+    pub async fn request_validation(
+        &self,
+        interactor: &mut Interactor,
+        job_id: &str,
+        agent_nonce: AgentId,
+        validator: &Address,
+    ) -> Result<Vec<u8>, HarnessError> {
+        let request_uri = format!("https://validator.openclaw.io/jobs/{}", job_id);
+        let hash = request_hash(&request_uri);
+        self.init_job(interactor, job_id, agent_nonce).await?;
+        let owner = &self.wallet_address;
+        self.validation_request(interactor, owner, job_id, validator, &request_uri, &hash)
+            .await?;
+        Ok(hash)
+    }
+
+    /// Answers the request `request_hash` with `response`, sent from the
+    /// `validator` named in it. Responses above [`MAX_VALIDATION_SCORE`] come
+    /// back as a [`HarnessError::ContractRevert`].
+    pub async fn validation_response(
+        &self,
+        interactor: &mut Interactor,
+        validator: &Address,
+        request_hash: &[u8],
+        response: u8,
+        tag: &str,
+    ) -> Result<String, HarnessError> {
+        let hash_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(request_hash);
+        let response_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(&[response]);
+        let empty_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new();
+        let tag_buf: ManagedBuffer<StaticApi> = ManagedBuffer::new_from_bytes(tag.as_bytes());
+
+        interactor
+            .tx()
+            .from(validator)
+            .to(&self.contract_address)
+            .gas(600_000_000)
+            .raw_call("validation_response")
+            .argument(&hash_buf)
+            .argument(&response_buf)
+            .argument(&empty_buf)
+            .argument(&empty_buf)
+            .argument(&tag_buf)
+            .returns(ReturnsHandledOrError::new().returns(ReturnsTxHash))
+            .run()
+            .await
+            .map(|tx_hash| hex::encode(tx_hash.as_bytes()))
+            .map_err(|err| HarnessError::ContractRevert {
+                status: err.status,
+                message: err.message,
+            })
+    }
+
+    /// Request hashes of every validation requested for `agent_nonce`, in
+    /// request order.
+    pub async fn get_agent_validations(&self, agent_nonce: AgentId) -> Vec<Vec<u8>> {
+        let result = vm_query(
+            &self.contract_address,
+            "get_agent_validations",
+            &[agent_nonce.to_be_bytes().to_vec()],
+        )
+        .await;
+        let mut hashes = Vec::new();
+        if let Some(bytes) = result.first() {
+            let mut decoder = NestedDecoder::new(bytes);
+            while !decoder.is_empty() {
+                hashes.push(
+                    decoder
+                        .read_buffer()
+                        .unwrap_or_else(|e| panic!("Undecodable validations: {}", e)),
+                );
+            }
+        }
+        hashes
+    }
+
+    /// Number of validations requested for `agent_nonce`; 0 when it has none.
+    pub async fn get_validation_count(&self, agent_nonce: AgentId) -> u64 {
+        self.get_agent_validations(agent_nonce).await.len() as u64
+    }
+
+    /// The stored request `request_hash`, or `None` if it was never made.
+    pub async fn get_validation_status(&self, request_hash: &[u8]) -> Option<ValidationStatus> {
+        let result = vm_query(
+            &self.contract_address,
+            "get_validation_status",
+            &[request_hash.to_vec()],
+        )
+        .await;
+        result
+            .first()
+            .filter(|bytes| !bytes.is_empty())
+            .map(|bytes| {
+                ValidationStatus::decode(bytes)
+                    .unwrap_or_else(|e| panic!("Undecodable validation status: {}", e))
+            })
+    }
+}
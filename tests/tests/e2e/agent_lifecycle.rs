@@ -12,25 +12,31 @@ use common::*;
 
 #[tokio::test]
 async fn test_agent_registration_cs() {
+    if let Err(e) = ensure_artifacts() {
+        panic!("{}", e);
+    }
+
     // 1. Connect to chain simulator
     let chain_id = get_simulator_chain_id().await;
     println!("✅ Chain Simulator connected — Chain ID: {}", chain_id);
+    assert_chain_advancing()
+        .await
+        .expect("Chain simulator is not producing blocks");
 
-    // 2. Create interactor with owner wallet
+    // 2-3. Create interactor with the owner wallet and fund it (100k EGLD)
     let mut interactor = Interactor::new(GATEWAY_URL).await;
-    let owner_wallet = interactor.register_wallet(Wallet::from_pem_file("alice.pem").unwrap());
-
-    // 3. Fund the owner
-    let owner_bech32 = address_to_bech32(&owner_wallet);
-    fund_address_on_simulator(&owner_bech32, "100000000000000000000000").await; // 100k EGLD
-    println!("✅ Owner funded: {}", owner_bech32);
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+    println!("✅ Owner funded: {}", address_to_bech32(&owner_wallet));
 
     // 4. Deploy Identity Registry
     let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
     println!("✅ Identity Registry deployed at: {}", identity.contract_address);
 
     // 5. Issue agent NFT token
-    identity.issue_token(&mut interactor, "OpenClawAgent", "OCAGENT").await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
     println!("✅ Agent token issued: OCAGENT");
 
     // 6. Register agent
@@ -40,7 +46,8 @@ async fn test_agent_registration_cs() {
             "market-research-bot",
             "https://research.openclaw.io",
         )
-        .await;
+        .await
+        .expect("Failed to register agent");
     println!("✅ Agent registered: market-research-bot");
 
     // 7. Generate blocks to finalize
@@ -74,3 +81,993 @@ async fn test_agent_profile_retrieval_cs() {
     assert!(profile["pricing"].is_object(), "Agent profile should have pricing");
     println!("✅ Agent profile: {}", profile["name"]);
 }
+
+#[tokio::test]
+async fn test_backend_profile_matches_chain_identity_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping profile consistency test");
+        return;
+    }
+
+    let RegisteredAgentSetup {
+        owner: owner_wallet,
+        identity,
+        ..
+    } = setup_registered_agent("research-bot").await;
+
+    let profile = AgentProfile::from_json(&backend_get_agent_profile().await);
+    if profile.name != "research-bot" {
+        println!(
+            "⚠️ Backend serves {} — run it with AGENT_NAME=research-bot and \
+             AGENT_WALLET_ADDRESS={} to check it against the chain",
+            profile.name,
+            address_to_bech32(&owner_wallet)
+        );
+        return;
+    }
+    identity.assert_profile_matches(&profile, &owner_wallet).await;
+    println!(
+        "✅ /api/agent matches on-chain research-bot registered by {}",
+        address_to_bech32(&owner_wallet)
+    );
+}
+
+#[tokio::test]
+async fn test_agent_reregistration_after_upgrade_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    // 1. Deploy, issue token and register the first agent
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    let pre_tx = identity
+        .register_agent(&mut interactor, "pre-upgrade-bot", "https://pre.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let pre_nonce = registered_agent(&pre_tx).await.expect("No registration event").nonce;
+    println!("✅ Pre-upgrade agent registered as #{}", pre_nonce);
+
+    // 2. Upgrade the contract in place
+    identity.upgrade(&mut interactor).await;
+    println!("✅ Identity Registry upgraded");
+
+    // 3. Register a second agent against the upgraded code
+    let post_tx = identity
+        .register_agent(&mut interactor, "post-upgrade-bot", "https://post.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let post_nonce = registered_agent(&post_tx).await.expect("No registration event").nonce;
+    assert_ne!(pre_nonce, post_nonce, "Upgrade reset the agent nonce sequence");
+    println!("✅ Post-upgrade agent registered as #{}", post_nonce);
+
+    // 4. Both agents must be readable — storage layout survived the upgrade
+    let pre = identity
+        .query_agent(pre_nonce)
+        .await
+        .expect("Pre-upgrade agent lost after upgrade");
+    assert_eq!(pre.name, "pre-upgrade-bot");
+    assert_eq!(identity.get_agent_details(pre_nonce).await, Some(pre));
+
+    let post = identity
+        .query_agent(post_nonce)
+        .await
+        .expect("Post-upgrade agent not found");
+    assert_eq!(post.name, "post-upgrade-bot");
+
+    let listed: Vec<AgentId> = identity.get_agents(0, 100).await.iter().map(|a| a.nonce).collect();
+    assert_eq!(listed, vec![pre_nonce, post_nonce], "get_agents lost an agent");
+    println!("✅ Pre- and post-upgrade agents coexist");
+}
+
+#[tokio::test]
+async fn test_agent_pubkey_roundtrip_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // Random key so any truncation or zero-padding in the nested encoding shows up
+    let public_key: [u8; 32] = rand::random();
+    identity
+        .register_agent_with_pubkey(
+            &mut interactor,
+            "pubkey-bot",
+            "https://research.openclaw.io",
+            &public_key,
+        )
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+
+    identity.assert_pubkey("pubkey-bot", &public_key).await;
+    println!("✅ Public key round-tripped: {}", hex::encode(public_key));
+}
+
+#[tokio::test]
+async fn test_register_gas_estimate_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    let args = IdentityRegistryInteractor::register_agent_args(
+        "gas-bot",
+        "https://research.openclaw.io",
+        &[0u8; 32],
+        &[],
+    );
+    let estimate = estimate_gas(
+        &owner_wallet,
+        &identity.contract_address,
+        "register_agent",
+        &args,
+    )
+    .await
+    .expect("Gas estimation failed");
+    println!("✅ register_agent estimate: {} gas", estimate);
+    assert!(
+        estimate < 600_000_000,
+        "register_agent estimate {} should be below the hardcoded 600M",
+        estimate
+    );
+
+    // The estimate must actually be enough to run the call
+    call_endpoint(
+        &mut interactor,
+        &owner_wallet,
+        &identity.contract_address,
+        "register_agent",
+        &args,
+    )
+    .await;
+    generate_blocks_on_simulator(3).await;
+    assert!(identity.find_agent("gas-bot").await.is_some());
+    println!("✅ register_agent succeeded with estimated gas");
+}
+
+#[tokio::test]
+async fn test_agent_services_decode_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // 0.50 USDC per query, 2 USDC per PDF report (6 decimals)
+    let services = vec![
+        ServiceConfigInput {
+            service_id: 1,
+            price: 500_000u64.into(),
+            token: "USDC-350c4e".to_string(),
+            nonce: 0,
+        },
+        ServiceConfigInput {
+            service_id: 2,
+            price: 2_000_000u64.into(),
+            token: "USDC-350c4e".to_string(),
+            nonce: 0,
+        },
+    ];
+    let tx_hash = identity
+        .register_agent_with_services(
+            &mut interactor,
+            "service-bot",
+            "https://research.openclaw.io",
+            &services,
+        )
+        .await
+        .expect("Failed to register agent");
+    let plain_tx = identity
+        .register_agent(&mut interactor, "no-service-bot", "https://plain.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+
+    let nonce = registered_agent(&tx_hash).await.expect("No registration event").nonce;
+    for service in &services {
+        let stored = identity
+            .get_agent_service_config(nonce, service.service_id)
+            .await
+            .unwrap_or_else(|| panic!("Service {} not stored", service.service_id));
+        assert_eq!(stored.amount, service.price);
+        assert_eq!(stored.token_identifier, service.token);
+    }
+    println!("✅ {} service configs decoded with prices", services.len());
+
+    let plain = registered_agent(&plain_tx).await.expect("No registration event").nonce;
+    assert_eq!(identity.get_agent_service_config(plain, 1).await, None);
+    println!("✅ Agent without services has no service config");
+}
+
+#[tokio::test]
+async fn test_duplicate_token_issuance_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("First issuance should succeed");
+    let token_id = identity.get_token_id().await;
+    assert!(token_id.starts_with("OCAGENT-"), "Unexpected token id: {}", token_id);
+    println!("✅ First issuance: {}", token_id);
+
+    // Same ticker again → typed error, not a panic
+    let err = identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect_err("Second issuance of the same ticker should fail");
+    assert_eq!(err, HarnessError::TokenAlreadyExists("OCAGENT".to_string()));
+    println!("✅ Duplicate issuance rejected: {}", err);
+
+    // The original token is untouched and still usable for registration
+    assert_eq!(identity.get_token_id().await, token_id);
+    identity
+        .register_agent(&mut interactor, "after-dup-bot", "https://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    assert!(identity.find_agent("after-dup-bot").await.is_some());
+    println!("✅ First token still usable after duplicate attempt");
+}
+
+#[tokio::test]
+async fn test_agent_price_roundtrip_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        identity,
+        agent_id: nonce,
+        ..
+    } = setup_registered_agent("priced-bot").await;
+
+    // 0.50 USDC (6 decimals)
+    let amount = num_bigint::BigUint::from(500_000u64);
+    let config = ServiceConfigInput {
+        service_id: 1,
+        price: amount.clone(),
+        token: "USDC-350c4e".to_string(),
+        nonce: 0,
+    };
+    identity.set_service_configs(&mut interactor, nonce, &[config]).await;
+    generate_blocks_on_simulator(3).await;
+
+    let stored = identity
+        .get_agent_service_config(nonce, 1)
+        .await
+        .expect("Service price not stored");
+    assert_eq!(stored.amount, amount);
+    assert_eq!(stored.token_identifier, "USDC-350c4e");
+    println!("✅ On-chain price: {} {}", stored.amount, stored.token_identifier);
+}
+
+#[tokio::test]
+async fn test_register_requires_https_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let mut identity =
+        IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // Flag on: the plain-HTTP URI is refused before anything is sent
+    identity.require_https = true;
+    let err = identity
+        .register_agent(&mut interactor, "plain-http-bot", "http://research.openclaw.io")
+        .await
+        .expect_err("http:// URI should be refused");
+    assert_eq!(
+        err,
+        HarnessError::InsecureUri("http://research.openclaw.io".to_string())
+    );
+    generate_blocks_on_simulator(3).await;
+    assert!(identity.find_agent("plain-http-bot").await.is_none());
+    println!("✅ http:// URI refused with require_https");
+
+    // Flag off: the same URI registers
+    identity.require_https = false;
+    identity
+        .register_agent(&mut interactor, "plain-http-bot", "http://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    assert!(identity.find_agent("plain-http-bot").await.is_some());
+    println!("✅ http:// URI accepted without require_https");
+}
+
+#[tokio::test]
+async fn test_deploy_is_reproducible_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let first = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    let second = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    assert_ne!(first.contract_address, second.contract_address);
+
+    let first_hash = get_code_hash(&first.contract_address)
+        .await
+        .expect("Failed to read code hash");
+    let second_hash = get_code_hash(&second.contract_address)
+        .await
+        .expect("Failed to read code hash");
+    assert_eq!(first_hash.len(), 32, "Code hash should be 32 bytes");
+    assert_eq!(
+        hex::encode(&first_hash),
+        hex::encode(&second_hash),
+        "Same WASM deployed twice should report the same code hash"
+    );
+    println!("✅ Both deploys report code hash {}", hex::encode(&first_hash));
+}
+
+#[tokio::test]
+async fn test_corrupt_wasm_deploy_fails_cleanly_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    // Random bytes with no WASM header, and a real artifact cut in half
+    let garbage: Vec<u8> = (0..64).flat_map(|_| rand::random::<[u8; 32]>()).collect();
+    let wasm_path = resolve_artifact(IDENTITY_WASM).unwrap_or_else(|e| panic!("{}", e));
+    let wasm_bytes = std::fs::read(&wasm_path).expect("Failed to read identity WASM");
+    let truncated = wasm_bytes[..wasm_bytes.len() / 2].to_vec();
+
+    for (label, code) in [("random bytes", garbage), ("truncated WASM", truncated)] {
+        match deploy_from_bytes(&mut interactor, &owner_wallet, &code, &[]).await {
+            Err(HarnessError::ContractRevert { message, .. }) => {
+                assert!(
+                    message.to_lowercase().contains("invalid"),
+                    "Deploying {} reverted for another reason: {}",
+                    label,
+                    message
+                );
+                println!("✅ Deploying {} reverted: {}", label, message);
+            }
+            Err(other) => panic!("Deploying {} failed untyped: {}", label, other),
+            Ok(address) => panic!(
+                "Deploying {} succeeded at {}",
+                label,
+                address_to_bech32(&address)
+            ),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_issue_token_callback_stores_token_id_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    assert!(identity.get_token_id().await.is_empty(), "Fresh registry already has a token");
+
+    // 1. issue_token calls the ESDT system contract asynchronously
+    let tx_hash = identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    let callback = wait_for_callback(&tx_hash, 20)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("✅ Callback arrived: {} -> {}", callback["sender"], callback["receiver"]);
+    let system_sc = bech32_to_address(ESDT_SYSTEM_SC).expect("ESDT system SC address is valid");
+    assert_cross_contract_call(
+        &tx_hash,
+        &identity.contract_address,
+        &system_sc,
+        "registerAndSetAllRoles",
+    )
+    .await;
+
+    // 2. The token id is written by the callback, not the initial call
+    assert_eq!(
+        callback["receiver"].as_str(),
+        Some(address_to_bech32(&identity.contract_address).as_str()),
+        "Callback went to the wrong contract"
+    );
+    let token_id = identity.get_token_id().await;
+    assert!(token_id.starts_with("OCAGENT-"), "Callback stored {:?}", token_id);
+    println!("✅ Callback stored token id {}", token_id);
+}
+
+#[tokio::test]
+async fn test_issue_token_reentry_before_callback_cs() {
+    use base64::Engine;
+
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let alice = Wallet::from_pem_file("alice.pem").unwrap();
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    generate_blocks_on_simulator(1).await;
+
+    // 1. Call issue_token twice before the first callback can come back
+    let mut tracker = NonceTracker::new();
+    let mut tx_hashes = Vec::new();
+    for ticker in ["OCAGENT", "OCAGAIN"] {
+        let nonce = tracker.next(&owner_wallet).await.expect("Failed to read owner nonce");
+        let mut unsigned = build_unsigned_payment_tx(
+            &owner_wallet,
+            &identity.contract_address,
+            TOKEN_ISSUE_COST.into(),
+            nonce,
+        )
+        .await;
+        let data = format!("issue_token@{}@{}", hex::encode("OpenClawAgent"), hex::encode(ticker));
+        unsigned["data"] = base64::engine::general_purpose::STANDARD.encode(data).into();
+        unsigned["gasLimit"] = FALLBACK_CALL_GAS_LIMIT.into();
+        let tx_hash = sign_and_submit(&alice, unsigned).await.expect("issue_token rejected");
+        tx_hashes.push(tx_hash);
+    }
+
+    // 2. The first issuance is pending, so re-entering it must revert
+    wait_for_callback(&tx_hashes[0], 20)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    let second = wait_for_tx(&tx_hashes[1], 20).await.expect("Second issue never finalized");
+    assert_eq!(second, "fail", "issue_token re-entered while its callback was pending");
+
+    // 3. Only the first callback wrote the token id
+    let token_id = identity.get_token_id().await;
+    assert!(token_id.starts_with("OCAGENT-"), "Callback stored {:?}", token_id);
+    println!("✅ Re-entrant issue_token reverted; token id stays {}", token_id);
+}
+
+#[tokio::test]
+async fn test_predicted_contract_address_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let nonce = get_account_nonce(&owner_wallet)
+        .await
+        .expect("Failed to read deployer nonce");
+    let predicted = compute_contract_address(&owner_wallet, nonce);
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    assert_eq!(
+        address_to_bech32(&identity.contract_address),
+        address_to_bech32(&predicted),
+        "Deployed address differs from the one predicted for nonce {}",
+        nonce
+    );
+    println!(
+        "✅ Deploy at nonce {} landed at predicted {}",
+        nonce,
+        address_to_bech32(&predicted)
+    );
+}
+
+#[tokio::test]
+async fn test_backend_pricing_matches_chain_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping pricing test");
+        return;
+    }
+
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // 1. Register the way scripts/register.ts does from agent.config.example.json:
+    //    its first service (id 0) at 0.50 USDC per call
+    let service = ServiceConfigInput {
+        service_id: 0,
+        price: 500_000u64.into(),
+        token: "USDC-350c4e".to_string(),
+        nonce: 0,
+    };
+    let tx_hash = identity
+        .register_agent_with_services(
+            &mut interactor,
+            "priced-bot",
+            "https://research.openclaw.io",
+            &[service],
+        )
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let nonce = registered_agent(&tx_hash).await.expect("No registration event").nonce;
+    let registered = identity
+        .get_agent_service_config(nonce, 0)
+        .await
+        .expect("Registered service has no price");
+
+    // 2. The backend serving that agent must charge what the chain says
+    let profile = AgentProfile::from_json(&backend_get_agent_profile().await);
+    if profile.name != "priced-bot" {
+        println!(
+            "⚠️ Backend serves {} — run it with AGENT_NAME=priced-bot to check its 402 price \
+             against the chain",
+            profile.name
+        );
+        return;
+    }
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let charged = parse_token_amount(&payment.amount, USDC_DECIMALS)
+        .unwrap_or_else(|| panic!("Unparseable 402 amount {:?}", payment.amount));
+    assert_eq!(payment.token, registered.token_identifier, "Backend charges a different token");
+    assert_eq!(
+        charged, registered.amount,
+        "Backend charges {} {} but priced-bot was registered at {} units",
+        payment.amount, payment.token, registered.amount
+    );
+    println!(
+        "✅ 402 price {} {} matches the registered {} units",
+        payment.amount, payment.token, registered.amount
+    );
+}
+
+#[tokio::test]
+async fn test_registration_events_cs() {
+    let RegisteredAgentSetup {
+        register_tx: tx_hash,
+        ..
+    } = setup_registered_agent("event-bot").await;
+
+    // Registration mints the agent NFT and announces the agent. The ABI
+    // promises no order between the two, so only their presence is checked
+    let tx = get_transaction(&tx_hash).await.expect("Failed to fetch registration tx");
+    let events = tx_event_identifiers(&tx);
+    for expected in ["ESDTNFTCreate", "agentRegistered"] {
+        assert!(
+            events.iter().any(|e| e == expected),
+            "Registration tx {} emitted no {}: {:?}",
+            tx_hash,
+            expected,
+            events
+        );
+    }
+    println!("✅ Registration tx {} emitted {:?}", tx_hash, events);
+}
+
+#[tokio::test]
+async fn test_register_agent_mints_nft_cs() {
+    let RegisteredAgentSetup {
+        owner: owner_wallet,
+        identity,
+        register_tx: tx_hash,
+        ..
+    } = setup_registered_agent("nft-bot").await;
+    let token_id = identity.get_token_id().await;
+    assert!(!token_id.is_empty(), "Agent token should be issued");
+
+    // register_agent mints the agent's NFT; its nonce is the agent nonce
+    let nonce = registered_agent(&tx_hash).await.expect("No registration event").nonce;
+    let nft = get_nft(&owner_wallet, &token_id, nonce)
+        .await
+        .expect("Failed to read account ESDTs")
+        .unwrap_or_else(|| panic!("{} not held by the owner", nft_identifier(&token_id, nonce)));
+    assert_eq!(nft.nonce, nonce);
+    println!("✅ Registration minted {} to the owner", nft_identifier(&token_id, nonce));
+}
+
+#[tokio::test]
+async fn test_query_agent_at_block_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        identity,
+        agent_id: nonce,
+        ..
+    } = setup_registered_agent("audit-bot").await;
+
+    // 1. Remember where the registry's shard was before the update
+    let shard = get_address_shard(&identity.contract_address)
+        .await
+        .expect("Failed to read registry shard");
+    let before_update = get_block_nonce(shard).await.expect("Failed to read block nonce");
+
+    // 2. Rename the agent and move it to a new URI
+    let args = IdentityRegistryInteractor::update_agent_args(
+        "audit-bot-v2",
+        "https://new.openclaw.io",
+        &[0u8; 32],
+        None,
+        None,
+    );
+    identity
+        .update_agent(&mut interactor, nonce, &args)
+        .await
+        .expect("Failed to update agent");
+    generate_blocks_on_simulator(3).await;
+
+    // 3. History keeps the old name; the latest state has the new one
+    let past = identity
+        .query_agent_at_block(nonce, before_update)
+        .await
+        .expect("Agent missing at the recorded block");
+    assert_eq!(past.name, "audit-bot");
+    let current = identity.query_agent(nonce).await.expect("Agent missing");
+    assert_eq!(current.name, "audit-bot-v2");
+    println!(
+        "✅ Block {} still shows {}; latest shows {}",
+        before_update, past.name, current.name
+    );
+}
+
+#[tokio::test]
+async fn test_register_gas_is_refunded_cs() {
+    let RegisteredAgentSetup {
+        register_tx: tx_hash,
+        ..
+    } = setup_registered_agent("refund-bot").await;
+
+    let tx = get_transaction(&tx_hash).await.expect("Failed to fetch register tx");
+    let gas_limit = tx["gasLimit"].as_u64().expect("Register tx missing gasLimit");
+    let gas_used = get_tx_gas_used(&tx_hash).await.expect("Failed to read gasUsed");
+    assert!(gas_used > 0, "Register should consume some gas");
+    // Burning the whole limit means the call ran out of gas rather than finished
+    assert!(
+        gas_used < gas_limit,
+        "register_agent consumed its entire {} gas limit",
+        gas_limit
+    );
+    println!(
+        "✅ register_agent used {} of {} gas, {} refunded",
+        gas_used,
+        gas_limit,
+        gas_limit - gas_used
+    );
+}
+
+#[tokio::test]
+async fn test_registered_agent_is_listed_cs() {
+    let RegisteredAgentSetup { identity, .. } = setup_registered_agent("listed-bot").await;
+
+    // Direct lookup alone would miss an agent left out of the enumerable set
+    let agent = identity.find_agent("listed-bot").await.expect("Agent not found by name");
+    assert!(identity.query_agent(agent.nonce).await.is_some(), "get_agent misses listed-bot");
+    identity.assert_agent_listed("listed-bot", 100).await;
+    println!("✅ listed-bot found by query_agent and in get_agents(0, 100)");
+}
+
+#[tokio::test]
+async fn test_duplicate_name_registration_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    let args = IdentityRegistryInteractor::register_agent_args(
+        "bot",
+        "https://research.openclaw.io",
+        &[0u8; 32],
+        &[],
+    );
+    identity
+        .register_agent_raw(&mut interactor, &args)
+        .await
+        .expect("First registration should succeed");
+    generate_blocks_on_simulator(3).await;
+
+    // A second registration of the same name either reverts or mints a
+    // separate agent; it must never overwrite the first one
+    let first = identity.find_agent("bot").await.expect("bot not registered");
+    let second = identity.register_agent_raw(&mut interactor, &args).await;
+    generate_blocks_on_simulator(3).await;
+
+    match second {
+        Err(err) => {
+            let message = err
+                .revert_message()
+                .unwrap_or_else(|| panic!("Expected a contract revert, got {:?}", err));
+            println!("✅ Second registration rejected: {}", message);
+            assert_eq!(identity.get_agent_count().await, 1, "bot was duplicated");
+        }
+        Ok(tx_hash) => {
+            let duplicate = registered_agent(&tx_hash).await.expect("No registration event");
+            assert_ne!(duplicate.nonce, first.nonce, "Second bot reused the first nonce");
+            assert_eq!(identity.get_agent_count().await, 2);
+            println!("✅ Registry allows duplicate names; second bot is #{}", duplicate.nonce);
+        }
+    }
+    assert_eq!(
+        identity.query_agent(first.nonce).await,
+        Some(first.details),
+        "First bot was overwritten"
+    );
+}
+
+#[tokio::test]
+async fn test_registration_requires_issued_token_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    // Deploy but skip issue_token
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    generate_blocks_on_simulator(3).await;
+
+    let args = IdentityRegistryInteractor::register_agent_args(
+        "early-bot",
+        "https://research.openclaw.io",
+        &[0u8; 32],
+        &[],
+    );
+    let err = identity
+        .register_agent_raw(&mut interactor, &args)
+        .await
+        .expect_err("Registering before issue_token should revert");
+    // Registration mints the agent's NFT, so it can't succeed without one
+    let message = err
+        .revert_message()
+        .unwrap_or_else(|| panic!("Expected a contract revert, got {:?}", err));
+    println!("✅ Registration before issue_token rejected: {}", message);
+
+    generate_blocks_on_simulator(3).await;
+    assert_eq!(identity.get_agent_count().await, 0, "early-bot was registered");
+}
+
+#[tokio::test]
+async fn test_agent_names_are_case_sensitive_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    identity
+        .register_agent(&mut interactor, "ResearchBot", "https://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+
+    // 1. Only the exact spelling resolves
+    let original = identity.find_agent("ResearchBot").await.expect("ResearchBot missing");
+    for other in ["researchbot", "RESEARCHBOT"] {
+        assert!(identity.find_agent(other).await.is_none(), "{} resolved", other);
+    }
+    println!("✅ Lookups of ResearchBot are case-sensitive");
+
+    // 2. A name differing only in case is a separate agent
+    let tx_hash = identity
+        .register_agent(&mut interactor, "researchbot", "https://other.openclaw.io")
+        .await
+        .expect("A differently-cased name should register");
+    generate_blocks_on_simulator(3).await;
+    let lower = registered_agent(&tx_hash).await.expect("No registration event");
+    assert_ne!(lower.nonce, original.nonce, "researchbot reused ResearchBot's nonce");
+    assert_eq!(
+        identity.query_agent(lower.nonce).await.map(|agent| agent.name).as_deref(),
+        Some("researchbot")
+    );
+    assert_eq!(
+        identity.query_agent(original.nonce).await,
+        Some(original.details),
+        "ResearchBot was overwritten"
+    );
+    println!("✅ researchbot registered alongside ResearchBot without clobbering it");
+}
+
+#[tokio::test]
+async fn test_shared_uri_is_detected_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    let uri = "https://shared.openclaw.io";
+    let first = identity
+        .register_agent(&mut interactor, "first-bot", uri)
+        .await
+        .expect("Failed to register first-bot");
+    generate_blocks_on_simulator(3).await;
+    let second = identity.register_agent(&mut interactor, "second-bot", uri).await;
+    generate_blocks_on_simulator(3).await;
+
+    match second {
+        Err(err) => {
+            // Rejecting the second agent keeps URIs unique on-chain
+            println!("✅ Registry rejected the shared URI: {}", err);
+        }
+        Ok(second) => {
+            let agents = [
+                registered_agent(&first).await.expect("No event for first-bot"),
+                registered_agent(&second).await.expect("No event for second-bot"),
+            ];
+            let duplicates = find_duplicate_uris(&agents);
+            assert_eq!(
+                duplicates,
+                vec![(
+                    uri.to_string(),
+                    vec!["first-bot".to_string(), "second-bot".to_string()]
+                )],
+                "Shared URI was not reported"
+            );
+            println!("✅ Registry allows shared URIs; {} flagged for both agents", uri);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_restored_snapshot_forgets_registration_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    generate_blocks_on_simulator(3).await;
+
+    // 1. Snapshot the registry, then register on top of it
+    let snapshot = snapshot_account(&identity.contract_address)
+        .await
+        .expect("Failed to snapshot registry");
+    identity
+        .register_agent(&mut interactor, "rollback-bot", "https://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    assert!(identity.find_agent("rollback-bot").await.is_some());
+
+    // 2. The simulator can't roll blocks back, so restore the snapshot instead
+    restore_account(&snapshot).await.expect("Failed to restore registry");
+    generate_blocks_on_simulator(1).await;
+
+    assert!(
+        identity.find_agent("rollback-bot").await.is_none(),
+        "Agent survived the restore"
+    );
+    assert_eq!(identity.get_agent_count().await, 0);
+    assert!(
+        !identity.get_token_id().await.is_empty(),
+        "State from before the snapshot was lost"
+    );
+    println!("✅ Restoring the pre-registration snapshot removed rollback-bot");
+}
+
+#[tokio::test]
+async fn test_agent_views_leave_storage_unchanged_cs() {
+    let RegisteredAgentSetup {
+        identity,
+        agent_id: nonce,
+        ..
+    } = setup_registered_agent("view-bot").await;
+
+    // 1. Record storage, then hammer the read-only views
+    let before = get_account_storage(&identity.contract_address)
+        .await
+        .expect("Failed to read registry storage");
+    for _ in 0..20 {
+        assert!(identity.query_agent(nonce).await.is_some());
+        assert!(identity.get_agent_details(nonce).await.is_some());
+    }
+    generate_blocks_on_simulator(1).await;
+
+    // 2. Views must not write: same keys, same values
+    let after = get_account_storage(&identity.contract_address)
+        .await
+        .expect("Failed to read registry storage");
+    assert_eq!(before.len(), after.len(), "query_agent changed the registry's storage size");
+    assert_eq!(before, after, "query_agent rewrote registry storage");
+    println!("✅ 20 query_agent calls left all {} storage keys untouched", after.len());
+}
+
+#[tokio::test]
+async fn test_noop_update_keeps_agent_unchanged_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        identity,
+        register_tx: tx_hash,
+        ..
+    } = setup_registered_agent("snapshot-bot").await;
+    let registered = registered_agent(&tx_hash).await.expect("No registration event");
+
+    let before = identity.query_agent(registered.nonce).await.expect("Agent missing");
+    // Re-submitting the current URI must not touch any field
+    identity
+        .update_agent_uri(&mut interactor, "snapshot-bot", &registered.uri)
+        .await
+        .expect("Failed to update agent");
+    generate_blocks_on_simulator(3).await;
+    let after = identity.query_agent(registered.nonce).await.expect("Agent missing");
+
+    assert_agent_eq(&before, &after);
+    println!("✅ snapshot-bot unchanged after no-op update");
+}
+
+#[tokio::test]
+async fn test_context_dump_after_deploy_cs() {
+    let mut interactor = TrackedInteractor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    generate_blocks_on_simulator(1).await;
+
+    let registries = Registries {
+        identity: &identity,
+        validation: None,
+        reputation: None,
+    };
+    let context = dump_context(&interactor, &registries).await;
+    for key in ["contracts", "owner", "agent_count", "block", "wallets"] {
+        assert!(context.get(key).is_some(), "Context dump missing {:?}: {}", key, context);
+    }
+    assert_eq!(
+        context["contracts"]["identity"],
+        address_to_bech32(&identity.contract_address)
+    );
+    assert_eq!(context["agent_count"], 0);
+    assert!(context["block"]["nonce"].as_u64().is_some(), "Block not captured: {}", context);
+
+    let path = write_context_dump("test_context_dump_after_deploy_cs", &context)
+        .expect("Failed to write context dump");
+    println!("✅ Context dumped to {}", path.display());
+}
+
+#[tokio::test]
+async fn test_agent_token_owned_by_contract_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    let token_id = identity.get_token_id().await;
+
+    // The contract must own the token to manage its roles
+    let token_owner = get_token_owner(&token_id).await.expect("Failed to read token owner");
+    assert_ne!(
+        token_owner,
+        address_to_bech32(&owner_wallet),
+        "{} is owned by the deploying wallet instead of the contract",
+        token_id
+    );
+    assert_eq!(token_owner, address_to_bech32(&identity.contract_address));
+    println!("✅ {} owned by the identity contract", token_id);
+}
+
+#[cfg(feature = "network-checks")]
+#[tokio::test]
+async fn test_agent_metadata_uri_resolves_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping metadata check");
+        return;
+    }
+
+    let profile = AgentProfile::from_json(&backend_get_agent_profile().await);
+    let Some(uri) = profile.metadata_uri.clone() else {
+        println!("⚠️ Agent has no metadataUri configured — skipping metadata check");
+        return;
+    };
+    assert_metadata_resolves(&profile)
+        .await
+        .unwrap_or_else(|e| panic!("Broken agent metadata link: {}", e));
+    println!("✅ Metadata URI {} resolves", uri);
+}
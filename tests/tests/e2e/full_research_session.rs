@@ -7,6 +7,9 @@
 //! 4. Send research query → receive SSE stream
 //! 5. Download report
 //!
+//! With `--features latency-budgets`, the flow is also timed phase by phase
+//! against `OPENCLAW_SESSION_BUDGET_MS`.
+//!
 //! Requires: chain simulator + backend running
 
 mod common;
@@ -14,21 +17,30 @@ use common::*;
 
 #[tokio::test]
 async fn test_full_research_session_cs() {
+    if let Err(e) = ensure_artifacts() {
+        panic!("{}", e);
+    }
+
     // ── Step 1: Chain Simulator Setup ──
     let chain_id = get_simulator_chain_id().await;
     println!("✅ Chain Simulator — Chain ID: {}", chain_id);
+    assert_chain_advancing()
+        .await
+        .expect("Chain simulator is not producing blocks");
 
     let mut interactor = Interactor::new(GATEWAY_URL).await;
-    let owner = interactor.register_wallet(Wallet::from_pem_file("alice.pem").unwrap());
-    let owner_bech32 = address_to_bech32(&owner);
-    fund_address_on_simulator(&owner_bech32, "100000000000000000000000").await;
+    let owner = register_and_fund_owner(&mut interactor).await;
 
     // ── Step 2: Deploy & Register ──
     let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner.clone()).await;
-    identity.issue_token(&mut interactor, "OpenClawAgent", "OCAGENT").await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
     identity
         .register_agent(&mut interactor, "research-bot", "https://research.openclaw.io")
-        .await;
+        .await
+        .expect("Failed to register agent");
     generate_blocks_on_simulator(3).await;
     println!("✅ On-chain setup complete");
 
@@ -57,28 +69,36 @@ async fn test_full_research_session_cs() {
     let job_id = confirm["jobId"].as_str().expect("Missing jobId");
     println!("✅ Payment confirmed — jobId: {}, tx: {}", job_id, &tx_hash[..10]);
 
-    // 3c. Send research query (now paid)
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(format!("{}/api/chat", BACKEND_URL))
-        .json(&serde_json::json!({
-            "message": "What are the top DeFi protocols on MultiversX?",
-            "sessionId": session_id
-        }))
-        .send()
+    // 3c. Send research query (now paid) and read the SSE stream to completion
+    let chunks = backend_stream_chat(session_id, "What are the top DeFi protocols on MultiversX?")
         .await
-        .expect("Failed to send research query");
+        .expect("Research stream failed");
+    let answer = collect_tokens(&chunks);
+    assert!(!answer.is_empty(), "Research stream should carry content");
+    validate_stream(&chunks).expect("Research stream is malformed");
+    println!(
+        "✅ Research query streamed — {} chunks, {} chars",
+        chunks.len(),
+        answer.len()
+    );
 
-    // Should get SSE stream (200) or content
-    assert!(
-        resp.status().is_success(),
-        "Paid session should accept query. Got: {}",
-        resp.status()
+    // The agent charges a flat rate: after a full answer the backend still
+    // quotes the price it charged in the 402, whatever the stream's length
+    let charge = PaymentRequired::from_json(&chat_resp).expect("Malformed 402 body");
+    let quote = backend_quote("What are the top DeFi protocols on MultiversX?").await;
+    assert_eq!(quote.token, charge.token, "Backend quotes a different token than it charged");
+    assert_eq!(
+        parse_token_amount(&quote.amount, USDC_DECIMALS),
+        parse_token_amount(&charge.amount, USDC_DECIMALS),
+        "Backend quotes {} after streaming {} chunks, but charged {}",
+        quote.amount,
+        chunks.len(),
+        charge.amount
     );
-    println!("✅ Research query accepted — streaming response");
+    println!("✅ Flat rate held for {} chunks: {} {}", chunks.len(), charge.amount, charge.token);
 
     // 3d. Try to download report (will 404 since no real report generated)
-    let download_resp = client
+    let download_resp = reqwest::Client::new()
         .get(format!("{}/api/download/{}", BACKEND_URL, job_id))
         .send()
         .await
@@ -94,3 +114,62 @@ async fn test_full_research_session_cs() {
     println!("   ├── API: 402 → payment → confirmed → query → stream");
     println!("   └── Chain ID: {}", chain_id);
 }
+
+#[tokio::test]
+async fn test_multi_query_research_session_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping multi-query session");
+        return;
+    }
+
+    // 1. Open and pay one session
+    let chat_resp = backend_start_chat("Research MultiversX staking").await;
+    let session_id = chat_resp["sessionId"].as_str().expect("Missing sessionId");
+    let tx_hash = format!("0x{}", hex::encode(rand::random::<[u8; 32]>()));
+    let confirm = backend_confirm_payment(session_id, &tx_hash).await;
+    assert_eq!(confirm["status"], "confirmed");
+
+    // 2. Three queries on the same payment
+    let queries = [
+        "What are the largest staking providers?",
+        "How has the staking APR changed this year?",
+        "Summarize the risks of liquid staking.",
+    ];
+    let stats = run_research_session(session_id, &queries)
+        .await
+        .expect("Research session failed");
+    assert_eq!(stats.len(), queries.len(), "Payment expired mid-session");
+    for stat in &stats {
+        assert!(stat.chars > 0, "Query {:?} streamed no content", stat.query);
+        println!(
+            "✅ {:?}: {} chunks, {} chars in {:?}",
+            stat.query, stat.chunks, stat.chars, stat.elapsed
+        );
+    }
+
+    // 3. One payment covers the whole session
+    assert_session_paid(session_id).await;
+    println!("✅ Session {} stayed paid across {} queries", session_id, stats.len());
+}
+
+#[cfg(feature = "latency-budgets")]
+#[tokio::test]
+async fn test_research_session_latency_budget_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping latency budget");
+        return;
+    }
+
+    let timing = measure_full_session("Summarize this week's MultiversX governance proposals")
+        .await
+        .unwrap_or_else(|e| panic!("Research session failed: {}", e));
+    let budget = SessionTiming::budget();
+    println!("⏱️ Session phases: {}", timing);
+    println!("⏱️ Budget:         {}", budget);
+
+    let over = timing.over_budget(&budget);
+    assert!(over.is_empty(), "Phases over budget:\n  {}", over.join("\n  "));
+    println!("✅ Every phase within budget");
+}
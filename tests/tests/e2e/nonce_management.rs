@@ -0,0 +1,132 @@
+//! E2E Test: Nonce Management
+//!
+//! Sends several txs from one wallet without waiting for blocks in between,
+//! relying on `NonceTracker` to hand out consecutive nonces, and checks the
+//! chain recorded them in submission order.
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_rapid_transfers_from_one_wallet_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let alice = Wallet::from_pem_file("alice.pem").unwrap();
+    register_and_fund_owner(&mut interactor).await;
+    let receiver = register_wallet(&mut interactor, Wallet::from_pem_file("bob.pem").unwrap());
+    generate_blocks_on_simulator(1).await;
+
+    // 1. Fire three transfers back-to-back, no block generation in between
+    let mut tracker = NonceTracker::new();
+    let mut hashes = Vec::new();
+    for i in 0..3 {
+        let hash = send_egld(&mut tracker, &alice, &receiver, 1_000_000_000_000_000)
+            .await
+            .unwrap_or_else(|e| panic!("Transfer {} was rejected: {}", i, e));
+        println!("✅ Transfer {} submitted: {}", i, hash);
+        hashes.push(hash);
+    }
+
+    // 2. All three must land
+    for hash in &hashes {
+        let status = wait_for_tx(hash, 10).await.expect("Transfer never finalized");
+        assert_eq!(status, "success", "Transfer {} failed", hash);
+    }
+    println!("✅ All 3 rapid transfers landed");
+}
+
+#[tokio::test]
+async fn test_tx_nonces_follow_submission_order_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let alice = Wallet::from_pem_file("alice.pem").unwrap();
+    let sender = register_and_fund_owner(&mut interactor).await;
+    let receiver = register_wallet(&mut interactor, Wallet::from_pem_file("bob.pem").unwrap());
+    generate_blocks_on_simulator(1).await;
+
+    // 1. Submit four transfers back-to-back
+    let first = get_account_nonce(&sender).await.expect("Failed to read nonce");
+    let mut tracker = NonceTracker::new();
+    let mut hashes = Vec::new();
+    for i in 0..4 {
+        let hash = send_egld(&mut tracker, &alice, &receiver, 1_000_000_000_000_000)
+            .await
+            .unwrap_or_else(|e| panic!("Transfer {} was rejected: {}", i, e));
+        hashes.push(hash);
+    }
+
+    // 2. The processed txs carry n, n+1, n+2, n+3 in the order they were sent
+    let mut nonces = Vec::new();
+    for hash in &hashes {
+        let status = wait_for_tx(hash, 10).await.expect("Transfer never finalized");
+        assert_eq!(status, "success", "Transfer {} failed", hash);
+        nonces.push(get_tx_nonce(hash).await.expect("Failed to read tx nonce"));
+    }
+    let expected: Vec<u64> = (first..first + 4).collect();
+    assert_eq!(nonces, expected, "On-chain nonces out of submission order");
+    println!("✅ Transfers landed with nonces {:?}", nonces);
+}
+
+#[tokio::test]
+async fn test_diagnose_nonce_gap_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let sender_wallet = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let sender = sender_wallet.to_address();
+    let receiver = register_wallet(&mut interactor, Wallet::from_pem_file("bob.pem").unwrap());
+    fund_address_on_simulator(&address_to_bech32(&sender), &egld(100)).await;
+    generate_blocks_on_simulator(1).await;
+
+    // Skip ahead 5 nonces so the tx sits in the pool waiting for the gap
+    let account_nonce = get_account_nonce(&sender).await.unwrap();
+    let mut tracker = NonceTracker::new();
+    tracker.set(&sender, account_nonce + 5);
+    let hash = send_egld(&mut tracker, &sender_wallet, &receiver, 1_000_000_000_000_000)
+        .await
+        .expect("Future-nonce tx should still be accepted into the pool");
+
+    let err = wait_for_tx(&hash, 3).await.expect_err("Gapped tx must not finalize");
+    let HarnessError::TxPending { diagnosis, .. } = err else {
+        panic!("Expected TxPending, got {:?}", err);
+    };
+    assert_eq!(
+        diagnosis.reason,
+        PendingReason::NonceGap {
+            tx_nonce: account_nonce + 5,
+            account_nonce,
+        }
+    );
+    println!("✅ Stuck tx diagnosed: {}", diagnosis.suggested_action);
+}
+
+#[tokio::test]
+async fn test_send_retries_after_nonce_desync_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let sender_wallet = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let sender = sender_wallet.to_address();
+    let receiver = register_wallet(&mut interactor, Wallet::from_pem_file("bob.pem").unwrap());
+    fund_address_on_simulator(&address_to_bech32(&sender), &egld(100)).await;
+    generate_blocks_on_simulator(1).await;
+
+    // Push the cached nonce far enough ahead that the gateway refuses the tx
+    let account_nonce = get_account_nonce(&sender).await.unwrap();
+    let mut tracker = NonceTracker::new();
+    tracker.set(&sender, account_nonce + 10_000);
+    let err = send_egld(&mut tracker, &sender_wallet, &receiver, 1_000_000_000_000_000)
+        .await
+        .expect_err("Desynced nonce should be rejected");
+    assert!(
+        matches!(err, HarnessError::NonceMismatch { .. }),
+        "Expected NonceMismatch, got {:?}",
+        err
+    );
+    println!("✅ Desynced send rejected: {}", err);
+
+    // Same desync through the wrapper: resynced and sent on the second try
+    tracker.set(&sender, account_nonce + 10_000);
+    let hash = send_egld_with_retry(&mut tracker, &sender_wallet, &receiver, 1_000_000_000_000_000)
+        .await
+        .expect("Wrapper should recover from the nonce desync");
+    assert_eq!(wait_for_tx(&hash, 10).await.unwrap(), "success");
+    assert_eq!(tracker.peek(&sender), Some(account_nonce + 1));
+    println!("✅ Tx {} recovered after nonce resync", hash);
+}
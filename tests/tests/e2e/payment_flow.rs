@@ -5,6 +5,21 @@
 //! 2. Simulate on-chain payment transaction
 //! 3. Confirm payment → expect session unlocked
 //! 4. Send follow-up message → expect SSE stream response
+//!
+//! Durability (`--features restart-checks`): a paid session and its job
+//! survive a backend restart. The default in-memory session store fails this;
+//! it needs a persistent store.
+//!
+//! Double spends: a tx hash that settled one session is refused (409) for any
+//! other.
+//!
+//! Abuse prevention: one session accepts `SESSION_QUERY_LIMIT` (default 10)
+//! queries per minute; past that the backend answers 429 with `Retry-After`
+//! instead of starting another agent run.
+//!
+//! Degraded mode: when the backend can't reach its chain API, confirming a
+//! payment answers 503 with an error and leaves the session unpaid. Covered
+//! against a second backend at `OPENCLAW_DEGRADED_BACKEND_URL`.
 
 mod common;
 use common::*;
@@ -84,3 +99,865 @@ async fn test_payment_gate_validation_cs() {
     assert_eq!(resp.status().as_u16(), 404, "Fake session should return 404");
     println!("✅ Fake session correctly rejected with 404");
 }
+
+#[tokio::test]
+async fn test_session_state_transitions_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping session state test");
+        return;
+    }
+
+    // Two independent sessions, both gated by 402
+    let first = backend_start_chat("Research AI market trends").await;
+    let second = backend_start_chat("Research DeFi lending rates").await;
+    let first_id = first["sessionId"].as_str().expect("Missing sessionId");
+    let second_id = second["sessionId"].as_str().expect("Missing sessionId");
+    assert_ne!(first_id, second_id, "Each chat should open its own session");
+
+    assert_session_unpaid(first_id).await;
+    assert_session_unpaid(second_id).await;
+    println!("✅ Both sessions unpaid after 402");
+
+    // Pay only the first one
+    let tx_hash = format!("sim-tx-{}", rand::random::<u64>());
+    let confirm = backend_confirm_payment(first_id, &tx_hash).await;
+    assert_eq!(confirm["status"], "confirmed", "Payment should be confirmed");
+
+    let paid = assert_session_paid(first_id).await;
+    assert_eq!(paid.job_id.as_deref(), confirm["jobId"].as_str());
+    println!("✅ Session {} paid — jobId: {:?}", first_id, paid.job_id);
+
+    // Paid state must not leak into the other session
+    assert_session_unpaid(second_id).await;
+    println!("✅ Session {} still unpaid", second_id);
+}
+
+#[tokio::test]
+async fn test_expired_sessions_are_collected_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping session expiry test");
+        return;
+    }
+
+    // Waiting out the default 30-minute TTL is impractical; run the backend
+    // with e.g. SESSION_TTL_MS=10000 to exercise this
+    let ttl = backend_session_ttl().await;
+    if ttl > std::time::Duration::from_secs(120) {
+        println!("⚠️ Session TTL is {:?} — skipping session expiry test", ttl);
+        return;
+    }
+
+    // 1. Leave a batch of 402s unpaid
+    let mut session_ids = Vec::new();
+    for i in 0..20 {
+        let body = backend_start_chat(&format!("Abandoned query {}", i)).await;
+        let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+        session_ids.push(payment.session_id);
+    }
+    let count_with_abandoned = backend_session_count().await;
+
+    // 2. Wait out the TTL plus one sweep
+    let ids = &session_ids;
+    poll_until(
+        "abandoned sessions to expire",
+        move || async move {
+            let mut remaining = 0;
+            for session_id in ids {
+                if backend_session_exists(session_id).await {
+                    remaining += 1;
+                }
+            }
+            Ok(remaining)
+        },
+        |remaining| *remaining == 0,
+        std::time::Duration::from_secs(1),
+        ttl + std::time::Duration::from_secs(70),
+    )
+    .await
+    .unwrap_or_else(|e| panic!("{}", e));
+
+    let count_after = backend_session_count().await;
+    assert!(
+        count_after < count_with_abandoned,
+        "Session count did not drop: {} → {}",
+        count_with_abandoned,
+        count_after
+    );
+    println!(
+        "✅ {} abandoned sessions collected after {:?} TTL ({} → {} sessions)",
+        session_ids.len(),
+        ttl,
+        count_with_abandoned,
+        count_after
+    );
+}
+
+#[tokio::test]
+async fn test_oversized_message_rejected_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping message length test");
+        return;
+    }
+
+    // Just over the per-message cap, and far over the request body cap
+    for (label, len) in [("10k+1", 10_001), ("1MB", 1024 * 1024)] {
+        let resp = backend_start_chat_raw(&"a".repeat(len)).await;
+        let status = resp.status().as_u16();
+        assert!(
+            status == 400 || status == 413,
+            "{} message should be rejected with 400/413, got {}",
+            label,
+            status
+        );
+        println!("✅ {} message rejected with {}", label, status);
+    }
+
+    // Rejection must not take the backend down
+    assert!(backend_health_check().await, "Backend unhealthy after oversized message");
+}
+
+#[tokio::test]
+async fn test_payment_recipient_is_agent_wallet_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping recipient test");
+        return;
+    }
+
+    // The backend is paid at its configured agent wallet, not at a contract
+    let profile = AgentProfile::from_json(&backend_get_agent_profile().await);
+    let Some(address) = profile.address else {
+        println!("⚠️ Backend has no agent address configured — skipping recipient test");
+        return;
+    };
+    let agent_wallet = bech32_to_address(&address)
+        .unwrap_or_else(|| panic!("Agent address {} is not valid bech32", address));
+
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    assert_payment_recipient(&payment, &agent_wallet);
+    println!("✅ 402 recipient matches the agent wallet {}", address);
+}
+
+#[tokio::test]
+async fn test_confirm_payments_batch_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping batch confirm test");
+        return;
+    }
+
+    // Open 5 sessions, each gated by its own 402
+    let mut pairs = Vec::new();
+    for i in 0..5 {
+        let body = backend_start_chat(&format!("Batch research query {}", i)).await;
+        let session_id = body["sessionId"].as_str().expect("Missing sessionId");
+        pairs.push((session_id.to_string(), format!("sim-tx-{}", rand::random::<u64>())));
+    }
+
+    let results = confirm_payments_batch(&pairs).await;
+    assert_eq!(results.len(), pairs.len());
+
+    let mut job_ids = std::collections::HashSet::new();
+    for ((session_id, _), result) in pairs.iter().zip(&results) {
+        let confirm = result
+            .as_ref()
+            .unwrap_or_else(|e| panic!("Confirm for {} failed: {}", session_id, e));
+        assert_eq!(confirm.status, "confirmed");
+        assert!(
+            job_ids.insert(confirm.job_id.clone()),
+            "Duplicate jobId {} across sessions",
+            confirm.job_id
+        );
+    }
+    println!("✅ 5 concurrent confirmations — {} distinct jobIds", job_ids.len());
+}
+
+#[tokio::test]
+async fn test_multi_token_settlement_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+
+    // 1. Payer holds two different ESDTs
+    let usdc: TokenId = "USDC-350c4e".to_string();
+    let wegld: TokenId = "WEGLD-a28c59".to_string();
+    let owner_bech32 = address_to_bech32(&owner_wallet);
+    fund_esdt(&owner_bech32, &usdc, &num_bigint::BigUint::from(5_000_000u64)).await;
+    fund_esdt(&owner_bech32, &wegld, &num_bigint::BigUint::from(10u64.pow(18))).await;
+    generate_blocks_on_simulator(1).await;
+
+    // 2. Settle both in one tx; the registry is deployed payable, so it
+    // accepts the tokens without an endpoint
+    let transfers = [
+        (usdc.clone(), num_bigint::BigUint::from(500_000u64)),
+        (wegld.clone(), num_bigint::BigUint::from(10u64.pow(17))),
+    ];
+    let tx_hash = multi_transfer(
+        &mut interactor,
+        &owner_wallet,
+        &identity.contract_address,
+        &transfers,
+        None,
+    )
+    .await
+    .expect("Multi-transfer should succeed");
+    generate_blocks_on_simulator(3).await;
+
+    // 3. Both balances arrived at the contract
+    for (token_id, amount) in &transfers {
+        let balance = get_esdt_balance(&identity.contract_address, token_id)
+            .await
+            .expect("Failed to read ESDT balance");
+        assert_eq!(&balance, amount, "{} did not arrive", token_id);
+    }
+    println!("✅ Multi-transfer {} settled {} tokens", tx_hash, transfers.len());
+}
+
+#[tokio::test]
+async fn test_confirm_rejects_payment_to_wrong_recipient_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping wrong-recipient test");
+        return;
+    }
+
+    // 1. Open a session and read who should be paid
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+
+    // 2. Pay a stranger instead of the agent
+    let payer = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    fund_address_on_simulator(&address_to_bech32(&payer.to_address()), &egld(10)).await;
+    let stranger = Wallet::from_private_key(&generate_random_private_key())
+        .unwrap()
+        .to_address();
+    assert_ne!(address_to_bech32(&stranger), payment.recipient);
+
+    let mut tracker = NonceTracker::new();
+    let tx_hash = simulate_payment(&mut tracker, &payer, &stranger, 10u128.pow(17))
+        .await
+        .expect("Payment to the stranger should go through on-chain");
+    println!("✅ Paid wrong recipient in tx {}", tx_hash);
+
+    // 3. The backend must not unlock the session for it
+    let confirm = backend_confirm_payment(&payment.session_id, &tx_hash).await;
+    if confirm["txVerification"] == "skipped_verification" {
+        println!("⚠️ Backend skips tx verification — skipping wrong-recipient check");
+        return;
+    }
+    assert_ne!(confirm["status"], "confirmed", "Payment to the wrong recipient was accepted");
+    assert!(confirm["error"].is_string(), "Expected an error, got: {}", confirm);
+    assert_session_unpaid(&payment.session_id).await;
+    println!("✅ Wrong-recipient payment rejected: {}", confirm["error"]);
+}
+
+#[tokio::test]
+async fn test_confirm_reports_reverted_payment_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping reverted-payment test");
+        return;
+    }
+
+    // 1. Open a session
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let recipient = bech32_to_address(&payment.recipient).expect("Invalid recipient address");
+
+    // 2. A contract is paid into a non-payable view; a wallet is sent a token
+    //    the payer doesn't hold. Either way the tx fails on-chain.
+    let payer = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    fund_address_on_simulator(&address_to_bech32(&payer.to_address()), &egld(10)).await;
+    let mut tracker = NonceTracker::new();
+    let tx_hash = if assert_is_contract_address(&recipient).is_ok() {
+        let view = "get_agent_count";
+        simulate_failing_payment(&mut tracker, &payer, &recipient, view, 10u128.pow(17)).await
+    } else {
+        let token = if payment.token == "EGLD" { "USDC-350c4e" } else { payment.token.as_str() };
+        let amount = num_bigint::BigUint::from(10u64).pow(6);
+        simulate_failing_esdt_payment(&mut tracker, &payer, &recipient, token, &amount).await
+    }
+    .unwrap_or_else(|e| panic!("{}", e));
+    println!("✅ Payment tx {} reverted on-chain", tx_hash);
+
+    // 3. The backend must pass the revert on, not confirm
+    let (status, confirm) = confirm_payment_raw(&payment.session_id, &tx_hash).await;
+    if confirm["txVerification"] == "skipped_verification" {
+        println!("⚠️ Backend skips tx verification — skipping reverted-payment check");
+        return;
+    }
+    assert_eq!(status, 400, "Reverted payment answered {}: {}", status, confirm);
+    assert_eq!(confirm["status"], "reverted");
+    let error = confirm["error"].as_str().unwrap_or_default();
+    assert!(error.contains("reverted"), "Error doesn't mention the revert: {}", error);
+    assert_session_unpaid(&payment.session_id).await;
+    println!("✅ Backend rejected the reverted payment: {}", error);
+}
+
+#[tokio::test]
+async fn test_tx_hash_cannot_pay_two_sessions_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping tx reuse test");
+        return;
+    }
+
+    // 1. Settle session A with a tx
+    let session_a = PaymentRequired::from_json(&backend_start_chat("Research EGLD staking").await)
+        .expect("Malformed 402 body")
+        .session_id;
+    let session_b = PaymentRequired::from_json(&backend_start_chat("Research ESDT tokens").await)
+        .expect("Malformed 402 body")
+        .session_id;
+    let tx_hash = hex::encode(rand::random::<[u8; 32]>());
+    let (status, body) = confirm_payment_raw(&session_a, &tx_hash).await;
+    if status != 200 {
+        println!("⚠️ Backend verifies txs on-chain ({}) — skipping tx reuse test", body);
+        return;
+    }
+    assert_session_paid(&session_a).await;
+
+    // 2. Replaying the same hash for session B is a double spend
+    let (status, body) = confirm_payment_raw(&session_b, &tx_hash).await;
+    assert_eq!(status, 409, "Reused tx answered {}: {}", status, body);
+    assert_eq!(body["status"], "tx_already_used");
+    assert_session_unpaid(&session_b).await;
+    assert_session_paid(&session_a).await;
+    println!("✅ Tx {} settled session A only; reuse for B rejected", tx_hash);
+}
+
+#[tokio::test]
+async fn test_partial_payment_rejected_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping partial-payment test");
+        return;
+    }
+
+    // 1. Open a session and work out the price in base units
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let decimals = if payment.token == "EGLD" { 18 } else { USDC_DECIMALS };
+    let required = parse_token_amount(&payment.amount, decimals)
+        .unwrap_or_else(|| panic!("Unparseable 402 amount {:?}", payment.amount));
+    let half = &required / 2u32;
+    let recipient = bech32_to_address(&payment.recipient)
+        .unwrap_or_else(|| panic!("402 recipient {} is not an address", payment.recipient));
+
+    // 2. Pay the agent only half of it
+    let payer = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let payer_bech32 = address_to_bech32(&payer.to_address());
+    fund_address_on_simulator(&payer_bech32, &egld(10)).await;
+    let mut tracker = NonceTracker::new();
+    let tx_hash = if payment.token == "EGLD" {
+        let half: u128 = half.to_string().parse().expect("Price does not fit in u128");
+        simulate_payment(&mut tracker, &payer, &recipient, half).await
+    } else {
+        fund_esdt(&payer_bech32, &payment.token, &required).await;
+        simulate_esdt_payment(&mut tracker, &payer, &recipient, &payment.token, &half).await
+    }
+    .expect("Partial payment should go through on-chain");
+    println!("✅ Paid {} of {} {} in tx {}", half, required, payment.token, tx_hash);
+
+    // 3. The backend must not unlock the session for it
+    let (status, confirm) = confirm_payment_raw(&payment.session_id, &tx_hash).await;
+    if confirm["txVerification"] == "skipped_verification" {
+        println!("⚠️ Backend skips tx verification — skipping partial-payment check");
+        return;
+    }
+    assert_ne!(confirm["status"], "confirmed", "Partial payment was accepted");
+    assert!(
+        (400..500).contains(&status),
+        "Expected a 4xx for a partial payment, got {}: {}",
+        status,
+        confirm
+    );
+    assert!(confirm["error"].is_string(), "Expected an error, got: {}", confirm);
+    assert_session_unpaid(&payment.session_id).await;
+    println!("✅ Partial payment rejected ({}): {}", status, confirm["status"]);
+}
+
+#[tokio::test]
+async fn test_quote_matches_payment_gate_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping quote test");
+        return;
+    }
+
+    // 1. Quoting doesn't open a session
+    let sessions_before = backend_session_count().await;
+    let quote = backend_quote("Research AI market trends").await;
+    assert_eq!(
+        backend_session_count().await,
+        sessions_before,
+        "A quote should not consume a session slot"
+    );
+    assert!(quote.session_id.is_empty());
+    println!("✅ Quote: {} {}", quote.amount, quote.token);
+
+    // 2. The real 402 asks for the same terms
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    assert_eq!(quote.amount, payment.amount);
+    assert_eq!(quote.token, payment.token);
+    assert_eq!(quote.recipient, payment.recipient);
+    println!("✅ Quote matches 402 for session {}", payment.session_id);
+}
+
+#[tokio::test]
+async fn test_canceled_session_cannot_be_paid_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping cancel test");
+        return;
+    }
+
+    // 1. Open a session and walk away from it
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    assert_session_unpaid(&payment.session_id).await;
+    backend_cancel_session(&payment.session_id)
+        .await
+        .expect("Unpaid session should be cancelable");
+    println!("✅ Session {} canceled", payment.session_id);
+
+    // 2. A late payment for it is refused
+    let tx_hash = format!("0x{}", hex::encode(rand::random::<[u8; 32]>()));
+    let (status, body) = confirm_payment_raw(&payment.session_id, &tx_hash).await;
+    assert!(
+        status == 404 || status == 410,
+        "Canceled session should be gone (404/410), got {}: {}",
+        status,
+        body
+    );
+    assert_ne!(body["status"], "confirmed");
+    println!("✅ Confirm on canceled session rejected with {}", status);
+
+    // 3. Canceling twice reports the session as gone
+    let err = backend_cancel_session(&payment.session_id)
+        .await
+        .expect_err("Second cancel should fail");
+    assert!(matches!(err, HarnessError::Backend { status: 404, .. }), "{:?}", err);
+}
+
+#[tokio::test]
+async fn test_client_signs_and_submits_payment_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let alice = Wallet::from_pem_file("alice.pem").unwrap();
+    let payer = register_and_fund_owner(&mut interactor).await;
+    let agent = register_wallet(&mut interactor, Wallet::from_pem_file("bob.pem").unwrap());
+    generate_blocks_on_simulator(1).await;
+
+    // 1. The client builds the transfer from the 402 terms, unsigned
+    let nonce = get_account_nonce(&payer).await.expect("Failed to read payer nonce");
+    let amount: u128 = 500_000_000_000_000_000; // 0.5 EGLD
+    let unsigned = build_unsigned_payment_tx(&payer, &agent, amount, nonce).await;
+    assert!(unsigned.get("signature").is_none());
+
+    // 2. The wallet signs and submits it
+    let tx_hash = sign_and_submit(&alice, unsigned)
+        .await
+        .expect("Signed payment was rejected");
+    let status = wait_for_tx(&tx_hash, 10).await.expect("Payment never finalized");
+    assert_eq!(status, "success", "Payment {} failed", tx_hash);
+    println!("✅ Client-signed payment landed: {}", tx_hash);
+}
+
+#[tokio::test]
+async fn test_payment_token_is_accepted_on_chain_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping accepted token test");
+        return;
+    }
+
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // 1. The agent accepts payment in the token its service is priced in
+    let service = ServiceConfigInput {
+        service_id: 1,
+        price: 500_000u64.into(),
+        token: "USDC-350c4e".to_string(),
+        nonce: 0,
+    };
+    let tx_hash = identity
+        .register_agent_with_services(
+            &mut interactor,
+            "paid-bot",
+            "https://research.openclaw.io",
+            &[service],
+        )
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let nonce = registered_agent(&tx_hash).await.expect("No registration event").nonce;
+    let accepted = identity
+        .get_agent_service_config(nonce, 1)
+        .await
+        .expect("Registered service has no price");
+
+    // 2. A token the agent isn't priced in would strand whatever the user sends
+    let profile = AgentProfile::from_json(&backend_get_agent_profile().await);
+    if profile.name != "paid-bot" {
+        println!(
+            "⚠️ Backend serves {} — run it with AGENT_NAME=paid-bot to check its 402 token \
+             against the chain",
+            profile.name
+        );
+        return;
+    }
+    let body = backend_start_chat("Research AI market trends").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    assert_eq!(
+        payment.token, accepted.token_identifier,
+        "Backend asks for {} but paid-bot is priced in {}",
+        payment.token, accepted.token_identifier
+    );
+    println!("✅ 402 token {} is the agent's on-chain payment token", payment.token);
+}
+
+#[tokio::test]
+async fn test_payment_conserves_funds_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let alice = Wallet::from_pem_file("alice.pem").unwrap();
+    let payer = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, payer.clone()).await;
+    generate_blocks_on_simulator(1).await;
+
+    // 1. Snapshot both sides
+    let payer_before = get_account_balance(&payer).await.expect("Failed to read payer");
+    let contract_before = get_account_balance(&identity.contract_address)
+        .await
+        .expect("Failed to read contract");
+
+    // 2. Pay the agent contract
+    let amount: u128 = 500_000_000_000_000_000; // 0.5 EGLD
+    let mut tracker = NonceTracker::new();
+    let tx_hash = simulate_payment(&mut tracker, &alice, &identity.contract_address, amount)
+        .await
+        .expect("Payment failed");
+    let fee = get_tx_fee(&tx_hash).await.expect("Failed to read fee");
+
+    // 3. Every unit the payer lost is either the payment or the fee
+    let amount = num_bigint::BigUint::from(amount);
+    let payer_after = get_account_balance(&payer).await.expect("Failed to read payer");
+    let contract_after = get_account_balance(&identity.contract_address)
+        .await
+        .expect("Failed to read contract");
+    assert_eq!(
+        payer_before,
+        &payer_after + &amount + &fee,
+        "Payer lost more than amount {} + fee {}",
+        amount,
+        fee
+    );
+    assert_eq!(
+        contract_after,
+        &contract_before + &amount,
+        "Contract did not receive exactly {}",
+        amount
+    );
+    println!("✅ Payment {} conserved funds: amount {} + fee {}", tx_hash, amount, fee);
+}
+
+#[tokio::test]
+async fn test_overpayment_is_kept_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+    let register_tx = identity
+        .register_agent(&mut interactor, "pricey-bot", "https://research.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let nonce = registered_agent(&register_tx).await.expect("No registration event").nonce;
+    let price = 100_000_000_000_000_000u64; // 0.1 EGLD
+    let config = ServiceConfigInput {
+        service_id: 1,
+        price: price.into(),
+        token: "EGLD-000000".to_string(),
+        nonce: 0,
+    };
+    identity.set_service_configs(&mut interactor, nonce, &[config]).await;
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let employer = WalletPool::new(&mut interactor, 1).await.addresses[0].clone();
+    generate_blocks_on_simulator(3).await;
+
+    // 1. Buy the priced service at three times its price
+    let employer_before = get_account_balance(&employer).await.expect("Failed to read employer");
+    let owner_before = get_account_balance(&owner).await.expect("Failed to read owner");
+    let amount = 3 * price;
+    let tx_hash = validation
+        .init_paid_job(&mut interactor, &employer, "overpaid-job", nonce, 1, amount)
+        .await
+        .expect("Overpaid job should be accepted");
+    generate_blocks_on_simulator(3).await;
+    let tx = get_transaction(&tx_hash).await.expect("Failed to read tx");
+    let fee = get_tx_fee(&tx_hash).await.expect("Failed to read fee");
+    let amount = num_bigint::BigUint::from(amount);
+
+    // 2. No refund: the employer loses the full payment, all of it reaches the owner
+    assert!(
+        !tx_events(&tx).iter().any(|event| event["identifier"] == "refund"),
+        "Registry documents no refunds, yet tx {} emitted one",
+        tx_hash
+    );
+    let employer_after = get_account_balance(&employer).await.expect("Failed to read employer");
+    let owner_after = get_account_balance(&owner).await.expect("Failed to read owner");
+    assert_eq!(
+        employer_before,
+        &employer_after + &amount + &fee,
+        "Employer should lose the full payment plus fee"
+    );
+    assert_eq!(owner_after, owner_before + &amount, "Owner should receive the full payment");
+    println!("✅ No refund: owner received all {} (price {})", amount, price);
+}
+
+#[tokio::test]
+async fn test_concurrent_payments_to_same_agent_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner = register_and_fund_owner(&mut interactor).await;
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner.clone()).await;
+    let pool = WalletPool::new(&mut interactor, 2).await;
+    generate_blocks_on_simulator(1).await;
+
+    let contract_before = get_account_balance(&identity.contract_address)
+        .await
+        .expect("Failed to read contract");
+
+    // 1. Both payers hit the agent at the same time, each with its own nonces
+    let amounts: [u128; 2] = [300_000_000_000_000_000, 700_000_000_000_000_000];
+    let payments = pool.wallets.iter().zip(amounts).map(|(wallet, amount)| {
+        let recipient = identity.contract_address.clone();
+        async move {
+            let mut tracker = NonceTracker::new();
+            simulate_payment(&mut tracker, wallet, &recipient, amount).await
+        }
+    });
+    let tx_hashes: Vec<String> = futures::future::join_all(payments)
+        .await
+        .into_iter()
+        .map(|result| result.expect("Concurrent payment failed"))
+        .collect();
+    println!("✅ Concurrent payments landed: {:?}", tx_hashes);
+
+    // 2. The contract received both, nothing lost to the race
+    let contract_after = get_account_balance(&identity.contract_address)
+        .await
+        .expect("Failed to read contract");
+    let total: u128 = amounts.iter().sum();
+    assert_eq!(
+        contract_after,
+        contract_before + num_bigint::BigUint::from(total),
+        "Contract balance should grow by the sum of both payments"
+    );
+    println!("✅ Contract balance grew by {}", total);
+
+    // 3. Each payment unlocks its own session and job. The txs paid the
+    //    registry, not the 402 recipient, so only a backend that skips
+    //    verification can accept them.
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping session confirmation");
+        return;
+    }
+    let mut job_ids = Vec::new();
+    for tx_hash in &tx_hashes {
+        let body = backend_start_chat("Research AI market trends").await;
+        let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+        let (status, confirm) = confirm_payment_raw(&payment.session_id, tx_hash).await;
+        if status != 200 {
+            println!("⚠️ Backend verifies txs on-chain ({}) — skipping confirmation", confirm);
+            return;
+        }
+        assert_eq!(confirm["status"], "confirmed", "Confirm failed: {}", confirm);
+        assert_eq!(confirm["txVerification"], "skipped_verification");
+        job_ids.push(confirm["jobId"].as_str().expect("Missing jobId").to_string());
+    }
+    assert_ne!(job_ids[0], job_ids[1], "Concurrent payments must get distinct jobs");
+    println!("✅ Distinct jobs: {:?}", job_ids);
+}
+
+#[tokio::test]
+async fn test_confirm_fails_loudly_when_chain_unreachable_cs() {
+    let Ok(base_url) = std::env::var(DEGRADED_BACKEND_URL_ENV) else {
+        println!("⚠️ {} not set — skipping degraded-chain test", DEGRADED_BACKEND_URL_ENV);
+        return;
+    };
+    if !health_check_at(&SlowClient::default(), &base_url).await {
+        println!("⚠️ Degraded backend not running — skipping degraded-chain test");
+        return;
+    }
+
+    // 1. Open a session on the backend that can't reach its chain API
+    let body: serde_json::Value = start_chat_raw_at(&base_url, "Research AI market trends")
+        .await
+        .json()
+        .await
+        .expect("Failed to parse chat response");
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+
+    // 2. Confirming must fail fast with a 5xx, not hang or report success
+    let tx_hash = "ab".repeat(32);
+    let (status, confirm) = tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        confirm_payment_raw_at(&base_url, &payment.session_id, &tx_hash),
+    )
+    .await
+    .expect("Confirmation hung while the chain was unreachable");
+
+    if confirm["txVerification"] == "skipped_verification" {
+        println!("⚠️ Degraded backend skips tx verification — nothing to check");
+        return;
+    }
+    assert!(
+        (500..600).contains(&status),
+        "Expected a 5xx for an unverifiable payment, got {}: {}",
+        status,
+        confirm
+    );
+    assert_ne!(confirm["status"], "confirmed", "Unverified payment was confirmed");
+    assert!(confirm["error"].is_string(), "Expected an error message, got: {}", confirm);
+    println!("✅ Unreachable chain reported as {}: {}", status, confirm["error"]);
+}
+
+#[tokio::test]
+async fn test_session_ids_are_unguessable_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping session id test");
+        return;
+    }
+
+    // /api/chat is capped per IP, so mint the bulk through /api/sessions
+    // (same generator) and check a real 402 id alongside them
+    let body = backend_start_chat("Research MultiversX sharding").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let mut ids = vec![payment.session_id];
+    for _ in 0..99 {
+        ids.push(backend_create_session().await);
+    }
+
+    assert_session_id_random(&ids);
+    println!("✅ {} session ids are unique and unguessable (e.g. {})", ids.len(), ids[0]);
+}
+
+#[tokio::test]
+async fn test_chat_cors_preflight_allowed_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping CORS test");
+        return;
+    }
+
+    // The web frontend posts to /api/chat cross-origin; its preflight must pass
+    let origin = frontend_origin();
+    assert_cors_allowed("/api/chat", &origin)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("✅ CORS preflight to /api/chat allowed from {}", origin);
+}
+
+#[tokio::test]
+async fn test_session_query_rate_limited_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping rate limit test");
+        return;
+    }
+
+    // 1. Pay for a session
+    let body = backend_start_chat("Research MultiversX staking").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let tx_hash = hex::encode(rand::random::<[u8; 32]>());
+    try_confirm_payment(&payment.session_id, &tx_hash)
+        .await
+        .expect("Payment confirmation failed");
+
+    // 2. Hammer it until the backend pushes back. The opening chat already
+    //    counted against the session's window.
+    let mut accepted = 0;
+    let mut limited = None;
+    for i in 0..DEFAULT_SESSION_QUERY_LIMIT * 2 {
+        let resp = backend_send_query_raw(&payment.session_id, &format!("query {}", i)).await;
+        if resp.status == 429 {
+            limited = Some(resp);
+            break;
+        }
+        assert_eq!(resp.status, 200, "Unexpected status on query {}: {}", i, resp.body);
+        accepted += 1;
+    }
+
+    // 3. It must refuse before the loop runs out, and say when to retry
+    let limited = limited.unwrap_or_else(|| {
+        panic!("Session accepted {} rapid queries without a 429", accepted)
+    });
+    assert!(
+        limited.body.contains("on this session"),
+        "429 came from another limiter: {}",
+        limited.body
+    );
+    assert!(limited.retry_after.is_some(), "429 without a Retry-After header");
+    println!(
+        "✅ Session rate limited after {} queries (retry after {:?})",
+        accepted, limited.retry_after
+    );
+}
+
+#[cfg(feature = "restart-checks")]
+#[tokio::test]
+async fn test_paid_session_survives_restart_cs() {
+    let Ok(restart_cmd) = std::env::var(BACKEND_RESTART_CMD_ENV) else {
+        println!("⚠️ {} not set — skipping restart test", BACKEND_RESTART_CMD_ENV);
+        return;
+    };
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping restart test");
+        return;
+    }
+
+    // 1. Pay for a session
+    let body = backend_start_chat("Research MultiversX validators").await;
+    let payment = PaymentRequired::from_json(&body).expect("Malformed 402 body");
+    let tx_hash = hex::encode(rand::random::<[u8; 32]>());
+    let confirm = try_confirm_payment(&payment.session_id, &tx_hash)
+        .await
+        .expect("Payment confirmation failed");
+
+    // 2. Restart the backend
+    restart_backend(&restart_cmd, std::time::Duration::from_secs(60))
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("✅ Backend restarted");
+
+    // 3. The session is still paid and still points at its job
+    assert!(
+        backend_session_exists(&payment.session_id).await,
+        "Session {} was lost in the restart",
+        payment.session_id
+    );
+    let session = assert_session_paid(&payment.session_id).await;
+    assert_eq!(session.job_id.as_deref(), Some(confirm.job_id.as_str()));
+    println!("✅ Session {} kept job {} across the restart", session.id, confirm.job_id);
+}
@@ -0,0 +1,102 @@
+//! E2E Test: Registry Limits
+//!
+//! Guards against contract-side resource problems that only show up at scale:
+//! 1. Paginated views stay within a gas budget as the registry grows
+//! 2. Oversized metadata is refused rather than stored
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+/// Ceiling for a single `get_agents` page, well under the node's query limit.
+const GET_AGENTS_GAS_BUDGET: u64 = 100_000_000;
+
+#[tokio::test]
+async fn test_get_agents_within_gas_budget_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // 1. Fill the registry with 50 agents
+    for i in 0..50 {
+        identity
+            .register_agent(
+                &mut interactor,
+                &format!("load-bot-{}", i),
+                &format!("https://bot{}.openclaw.io", i),
+            )
+            .await
+            .expect("Failed to register agent");
+    }
+    generate_blocks_on_simulator(3).await;
+    println!("✅ 50 agents registered");
+
+    // 2. Each page must stay inside the budget, regardless of registry size
+    for offset in [0u64, 20, 40] {
+        let args = [offset.to_be_bytes().to_vec(), 20u64.to_be_bytes().to_vec()];
+        let page = query_with_gas_limit(
+            &owner_wallet,
+            &identity.contract_address,
+            "get_agents",
+            &args,
+            GET_AGENTS_GAS_BUDGET,
+        )
+        .await
+        .unwrap_or_else(|e| panic!("get_agents page at {} over budget: {}", offset, e));
+        let agents = page
+            .first()
+            .map(|bytes| AgentListEntry::decode_list(bytes).expect("Undecodable get_agents page"))
+            .unwrap_or_default();
+        assert_eq!(agents.len(), 20, "Page at offset {} should be full", offset);
+        println!("✅ get_agents({}, 20) returned {} agents within budget", offset, agents.len());
+    }
+}
+
+#[tokio::test]
+async fn test_oversized_metadata_rejected_cs() {
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner_wallet = register_and_fund_owner(&mut interactor).await;
+
+    let identity = IdentityRegistryInteractor::deploy(&mut interactor, owner_wallet.clone()).await;
+    identity
+        .issue_token(&mut interactor, "OpenClawAgent", "OCAGENT")
+        .await
+        .expect("Failed to issue agent token");
+
+    // 1. A 100KB metadata value, far past anything an agent needs
+    let huge = vec![b'x'; 100 * 1024];
+    let args = IdentityRegistryInteractor::register_agent_args_with_metadata(
+        "bloat-bot",
+        "https://research.openclaw.io",
+        &[0u8; 32],
+        &[("description", huge.as_slice())],
+        &[],
+    );
+    let err = identity
+        .register_agent_raw(&mut interactor, &args)
+        .await
+        .expect_err("Registering 100KB of metadata should fail");
+
+    // 2. Either the size check or the gas limit stops it — never a panic
+    let message = err
+        .revert_message()
+        .unwrap_or_else(|| panic!("Expected a contract revert, got {:?}", err))
+        .to_lowercase();
+    assert!(
+        ["too large", "too long", "size", "limit", "out of gas", "not enough gas"]
+            .iter()
+            .any(|hint| message.contains(hint)),
+        "Unexpected revert for oversized metadata: {}",
+        message
+    );
+    println!("✅ Oversized metadata rejected: {}", message);
+
+    generate_blocks_on_simulator(3).await;
+    assert_eq!(identity.get_agent_count().await, 0, "bloat-bot was stored");
+}
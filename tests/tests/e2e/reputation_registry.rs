@@ -0,0 +1,158 @@
+//! E2E Test: Reputation Registry
+//!
+//! Rates agents through the reputation registry and reads back the
+//! aggregated leaderboard, per-agent running averages, and batched reads.
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_reputation_leaderboard_order_cs() {
+    // 1. Identity registry with three agents
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id: gold,
+        ..
+    } = setup_registered_agent("gold-bot").await;
+    for name in ["silver-bot", "bronze-bot"] {
+        identity
+            .register_agent(&mut interactor, name, &format!("https://{}.openclaw.io", name))
+            .await
+            .expect("Failed to register agent");
+    }
+    generate_blocks_on_simulator(3).await;
+    let silver = identity.find_agent("silver-bot").await.expect("silver-bot not registered").nonce;
+    let bronze = identity.find_agent("bronze-bot").await.expect("bronze-bot not registered").nonce;
+
+    // 2. Validation + reputation registries
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let reputation = ReputationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &validation.contract_address,
+        &identity.contract_address,
+    )
+    .await;
+
+    // 3. Different ratings per agent, registered out of leaderboard order
+    let ratings = [(bronze, 2), (gold, 5), (silver, 4)];
+    for (i, (agent_id, rating)) in ratings.iter().enumerate() {
+        reputation
+            .give_feedback(&mut interactor, &format!("job-{}", i), *agent_id, *rating)
+            .await;
+    }
+    generate_blocks_on_simulator(3).await;
+
+    // 4. Leaderboard is sorted by average, best first
+    let leaderboard = get_all_reputations(&identity, &reputation).await;
+    let order: Vec<AgentId> = leaderboard.iter().map(|(id, _)| *id).collect();
+    assert_eq!(order, vec![gold, silver, bronze]);
+    assert!(leaderboard
+        .windows(2)
+        .all(|pair| pair[0].1.average >= pair[1].1.average));
+    for (agent_id, view) in &leaderboard {
+        println!("✅ Agent {}: average {} over {} jobs", agent_id, view.average, view.total_jobs);
+    }
+}
+
+#[tokio::test]
+async fn test_reputation_average_tracks_every_rating_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id,
+        ..
+    } = setup_registered_agent("rated-bot").await;
+
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let reputation = ReputationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &validation.contract_address,
+        &identity.contract_address,
+    )
+    .await;
+
+    // After every rating the reported average must equal the running mean
+    let mut given = Vec::new();
+    for (i, rating) in [5u64, 3, 4, 1, 5, 2].into_iter().enumerate() {
+        reputation
+            .give_feedback(&mut interactor, &format!("avg-job-{}", i), agent_id, rating)
+            .await;
+        generate_blocks_on_simulator(1).await;
+        given.push(rating);
+
+        let view = reputation.get_reputation(agent_id).await;
+        check_running_average(&view, &given)
+            .unwrap_or_else(|e| panic!("After rating #{}: {}", i + 1, e));
+        println!("✅ {} ratings: average {}", given.len(), view.average);
+    }
+}
+
+#[tokio::test]
+async fn test_reputations_batch_read_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id: first,
+        ..
+    } = setup_registered_agent("batch-a").await;
+    let mut agent_ids = vec![first];
+    for name in ["batch-b", "batch-c"] {
+        identity
+            .register_agent(&mut interactor, name, &format!("https://{}.openclaw.io", name))
+            .await
+            .expect("Failed to register agent");
+    }
+    generate_blocks_on_simulator(3).await;
+    for name in ["batch-b", "batch-c"] {
+        agent_ids.push(identity.find_agent(name).await.expect("Agent not registered").nonce);
+    }
+
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let reputation = ReputationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &validation.contract_address,
+        &identity.contract_address,
+    )
+    .await;
+
+    // 1. A distinct rating per agent
+    for (i, (agent_id, rating)) in agent_ids.iter().zip([3u64, 5, 1]).enumerate() {
+        reputation
+            .give_feedback(&mut interactor, &format!("batch-job-{}", i), *agent_id, rating)
+            .await;
+    }
+    generate_blocks_on_simulator(3).await;
+
+    // 2. One batched read returns all three, in the order asked
+    let batch = reputation.get_reputations_batch(&agent_ids).await;
+    assert_eq!(batch.len(), 3);
+    assert_eq!(batch.iter().map(|v| v.average).collect::<Vec<_>>(), vec![3, 5, 1]);
+    for (agent_id, view) in agent_ids.iter().zip(&batch) {
+        assert_eq!(*view, reputation.get_reputation(*agent_id).await);
+        println!("✅ Agent {}: average {} over {} jobs", agent_id, view.average, view.total_jobs);
+    }
+}
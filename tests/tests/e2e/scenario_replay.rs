@@ -0,0 +1,25 @@
+//! E2E Test: Scenario Replay
+//!
+//! Runs the JSON scenarios under `scenarios/` step by step against the chain
+//! simulator; each script carries its own final-state expectations.
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_replay_register_and_pay_scenario_cs() {
+    let script = ScenarioScript::load("scenarios/register_and_pay.json")
+        .expect("Failed to load scenario");
+    println!("Scenario: {}", script.description);
+
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let identity = script
+        .run(&mut interactor)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+
+    assert!(identity.find_agent("scenario-bot").await.is_some());
+    println!("✅ {} steps replayed, final state matches", script.steps.len());
+}
@@ -0,0 +1,133 @@
+//! E2E Test: Simulator Funding
+//!
+//! Verifies the set-state based funding helpers used by the other suites:
+//! 1. ESDT balances can be pre-loaded without minting
+//! 2. The default owner funding applies the expected balance
+//! 3. Registered wallets are tracked per interactor
+//! 4. Spending past the funded balance yields a typed error
+//! 5. Wallets loaded from a role manifest are registered and funded by role
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_fund_esdt_cs() {
+    let wallet = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let address = wallet.to_address();
+    let address_bech32 = address_to_bech32(&address);
+    fund_address_on_simulator(&address_bech32, &egld(1)).await;
+
+    let token_id = "USDC-350c4e";
+    let amount: num_bigint::BigUint = "2500000".parse().unwrap();
+    fund_esdt(&address_bech32, token_id, &amount).await;
+    generate_blocks_on_simulator(1).await;
+
+    let balance = get_esdt_balance(&address, token_id)
+        .await
+        .expect("Failed to read ESDT balance");
+    assert_eq!(balance, amount, "ESDT balance was not applied");
+    println!("✅ {} funded with {} {}", address_bech32, balance, token_id);
+
+    // EGLD balance survives the ESDT set-state
+    let egld_balance = get_account_balance(&address).await.unwrap();
+    assert_eq!(egld_balance.to_string(), egld(1));
+    println!("✅ EGLD balance preserved: {}", egld_balance);
+}
+
+#[tokio::test]
+async fn test_fund_default_owner_cs() {
+    assert_eq!(egld(1), "1000000000000000000");
+
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let owner = register_and_fund_owner(&mut interactor).await;
+    generate_blocks_on_simulator(1).await;
+
+    let balance = get_account_balance(&owner).await.expect("Failed to read balance");
+    assert_eq!(
+        balance.to_string(),
+        egld(DEFAULT_FUNDING_EGLD),
+        "Owner should hold the default {} EGLD",
+        DEFAULT_FUNDING_EGLD
+    );
+    println!("✅ Owner funded with default balance: {}", balance);
+}
+
+#[tokio::test]
+async fn test_registered_wallets_cs() {
+    let mut interactor = TrackedInteractor::new(GATEWAY_URL).await;
+
+    let pool = WalletPool::new(&mut interactor, 2).await;
+    let wallets = interactor.registered_wallets();
+    for address in &pool.addresses {
+        assert!(wallets.contains(address), "{} not listed", address_to_bech32(address));
+    }
+
+    let owner = register_and_fund_owner(&mut interactor).await;
+    let wallets = interactor.registered_wallets();
+    assert_eq!(wallets.last(), Some(&owner));
+    println!("✅ {} wallets registered", wallets.len());
+}
+
+#[tokio::test]
+async fn test_underfunded_transfer_reports_amounts_cs() {
+    let wallet = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let address = wallet.to_address();
+    fund_address_on_simulator(&address_to_bech32(&address), &egld(1)).await;
+    generate_blocks_on_simulator(1).await;
+
+    let recipient = Wallet::from_private_key(&generate_random_private_key())
+        .unwrap()
+        .to_address();
+    let amount: u128 = egld(2).parse().unwrap();
+    let mut tracker = NonceTracker::new();
+    let err = send_egld(&mut tracker, &wallet, &recipient, amount)
+        .await
+        .expect_err("Sending 2 EGLD from a 1 EGLD account must fail");
+
+    match &err {
+        HarnessError::InsufficientFunds {
+            required,
+            available,
+        } => {
+            assert!(*required > num_bigint::BigUint::from(amount), "Fee missing from {}", required);
+            assert_eq!(available.to_string(), egld(1));
+        }
+        other => panic!("Expected InsufficientFunds, got {:?}", other),
+    }
+    println!("✅ Underfunded transfer rejected: {}", err);
+}
+
+#[tokio::test]
+async fn test_wallet_manifest_roles_cs() {
+    let dir = scratch_dir("wallet-manifest");
+    let roles = ["owner", "validator", "user"];
+    let mut manifest = serde_json::Map::new();
+    for role in roles {
+        write_pem(dir.join(format!("{}.pem", role)), &generate_random_private_key())
+            .expect("Failed to write PEM");
+        manifest.insert(role.to_string(), format!("{}.pem", role).into());
+    }
+    let manifest_path = dir.join("wallets.json");
+    std::fs::write(&manifest_path, serde_json::Value::Object(manifest).to_string())
+        .expect("Failed to write manifest");
+
+    let wallets = load_wallets_from_manifest(&manifest_path).expect("Failed to load manifest");
+    let mut interactor = Interactor::new(GATEWAY_URL).await;
+    let addresses = register_roles(&mut interactor, &wallets).await;
+    generate_blocks_on_simulator(1).await;
+
+    let names: Vec<&str> = addresses.iter().map(|(role, _)| role.as_str()).collect();
+    assert_eq!(names, ["owner", "user", "validator"]);
+    let distinct: std::collections::HashSet<_> = addresses.iter().map(|(_, a)| a).collect();
+    assert_eq!(distinct.len(), roles.len(), "Roles share an address");
+
+    for (role, address) in &addresses {
+        let balance = get_account_balance(address)
+            .await
+            .expect("Failed to read balance");
+        assert_eq!(balance.to_string(), egld(DEFAULT_FUNDING_EGLD), "{} was not funded", role);
+        println!("✅ {} → {} funded with {}", role, address_to_bech32(address), balance);
+    }
+}
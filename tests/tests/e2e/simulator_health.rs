@@ -0,0 +1,103 @@
+//! E2E Test: Chain Simulator Health
+//!
+//! Sanity checks on the simulator itself, run before trusting any
+//! contract-level results:
+//! 1. Block production actually advances the round, one nonce per block
+//! 2. The backend is configured for the simulator's chain
+//! 3. The gateway is detected as the simulator, so simulator-only helpers work
+//! 4. The simulator is recent enough for the contracts' features
+//! 5. Blocks are produced fast enough that tx waits won't crawl
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_chain_advancing_cs() {
+    let chain_id = get_simulator_chain_id().await;
+    println!("✅ Chain Simulator connected — Chain ID: {}", chain_id);
+
+    assert_chain_advancing()
+        .await
+        .expect("Healthy simulator should advance on generate-blocks");
+    println!("✅ Block production is advancing");
+}
+
+#[tokio::test]
+async fn test_block_nonce_tracks_generated_blocks_cs() {
+    let before = get_current_block().await.expect("Failed to read current block");
+    generate_blocks_on_simulator(5).await;
+    let after = get_current_block().await.expect("Failed to read current block");
+
+    assert_eq!(
+        after.nonce,
+        before.nonce + 5,
+        "Generating 5 blocks moved the nonce {} -> {}",
+        before.nonce,
+        after.nonce
+    );
+    assert!(after.round > before.round);
+    assert!(after.epoch >= before.epoch);
+    println!("✅ Block nonce {} -> {} after 5 blocks", before.nonce, after.nonce);
+}
+
+#[tokio::test]
+async fn test_block_generation_rate_cs() {
+    let rate = benchmark_block_generation(20)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("Block generation: {}", rate);
+
+    let min = BlocksPerSec(min_blocks_per_sec());
+    assert!(rate >= min, "Simulator produced {}, below the {} floor", rate, min);
+    println!("✅ Simulator generates {} (minimum {})", rate, min);
+}
+
+#[tokio::test]
+async fn test_backend_chain_id_matches_simulator_cs() {
+    let is_healthy = backend_health_check().await;
+    if !is_healthy {
+        println!("⚠️ Backend not running — skipping chain id check");
+        return;
+    }
+
+    let simulator_chain_id = get_simulator_chain_id().await;
+    let backend_chain_id = backend_get_chain_id().await;
+    assert_eq!(
+        backend_chain_id, simulator_chain_id,
+        "Backend is configured for chain {:?} but the simulator runs {:?}; payments signed \
+         for one cannot be verified on the other (set MULTIVERSX_CHAIN_ID)",
+        backend_chain_id, simulator_chain_id
+    );
+    println!("✅ Backend and simulator agree on chain id {}", simulator_chain_id);
+}
+
+#[tokio::test]
+async fn test_network_mode_is_simulator_cs() {
+    let mode = detect_network_mode().await.expect("Gateway should be reachable");
+    assert_eq!(
+        mode,
+        NetworkMode::Simulator,
+        "{} does not serve /simulator/* endpoints",
+        GATEWAY_URL
+    );
+    ensure_simulator("generate_blocks_on_simulator")
+        .await
+        .expect("Simulator-only helpers should be allowed");
+    println!("✅ {} detected as {:?}", GATEWAY_URL, mode);
+}
+
+#[tokio::test]
+async fn test_gateway_meets_min_version_cs() {
+    let version = get_gateway_version().await.expect("Gateway version not reported");
+    assert!(
+        parse_version(&version).is_some(),
+        "Gateway version {:?} does not parse",
+        version
+    );
+    require_min_version(MIN_GATEWAY_VERSION)
+        .await
+        .unwrap_or_else(|e| panic!("{}", e));
+    println!("✅ Gateway {} meets minimum {}", version, MIN_GATEWAY_VERSION);
+}
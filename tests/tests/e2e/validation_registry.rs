@@ -0,0 +1,162 @@
+//! E2E Test: Validation Registry
+//!
+//! Exercises the validation registry against agents registered in the
+//! identity registry:
+//! 1. Validation requests are tracked per agent
+//! 2. Ratings of validated jobs reach the reputation registry
+//! 3. Responses outside 0..=MAX_VALIDATION_SCORE are rejected
+//!
+//! Requires: chain simulator running
+
+mod common;
+use common::*;
+
+#[tokio::test]
+async fn test_validation_count_per_agent_cs() {
+    // 1. Identity registry with two agents
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id: busy,
+        ..
+    } = setup_registered_agent("busy-bot").await;
+    identity
+        .register_agent(&mut interactor, "quiet-bot", "https://quiet.openclaw.io")
+        .await
+        .expect("Failed to register agent");
+    generate_blocks_on_simulator(3).await;
+    let quiet = identity.find_agent("quiet-bot").await.expect("quiet-bot not registered").nonce;
+
+    // 2. Validation registry bound to it, and a validator to ask
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    assert_eq!(validation.get_validation_count(busy).await, 0);
+    let validator = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let validator = register_wallet(&mut interactor, validator);
+    fund_default(&address_to_bech32(&validator)).await;
+
+    // 3. Three validation requests for one agent, one for the other
+    for (i, agent_nonce) in [busy, busy, busy, quiet].into_iter().enumerate() {
+        let job_id = format!("count-job-{}", i);
+        validation
+            .request_validation(&mut interactor, &job_id, agent_nonce, &validator)
+            .await
+            .expect("Validation request should be accepted");
+    }
+    generate_blocks_on_simulator(3).await;
+
+    assert_eq!(validation.get_validation_count(busy).await, 3);
+    assert_eq!(validation.get_validation_count(quiet).await, 1);
+    println!("✅ Validation counts: busy-bot=3, quiet-bot=1");
+}
+
+#[tokio::test]
+async fn test_validated_job_feeds_reputation_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id,
+        ..
+    } = setup_registered_agent("rated-bot").await;
+
+    // 1. The reputation registry is wired to the validation registry at deploy
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let reputation = ReputationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &validation.contract_address,
+        &identity.contract_address,
+    )
+    .await;
+    assert_eq!(
+        reputation.get_validation_contract_address().await,
+        validation.contract_address,
+        "Reputation registry reads jobs from another validation registry"
+    );
+
+    // 2. Validate a job, then rate it: the rating lands on the agent
+    let hash = validation
+        .request_validation(&mut interactor, "rated-job", agent_id, &owner_wallet)
+        .await
+        .expect("Validation request should be accepted");
+    validation
+        .validation_response(&mut interactor, &owner_wallet, &hash, 85, "quality")
+        .await
+        .expect("Validation response should be accepted");
+    generate_blocks_on_simulator(3).await;
+    reputation.give_feedback(&mut interactor, "rated-job", agent_id, 5).await;
+    generate_blocks_on_simulator(3).await;
+
+    let view = reputation.get_reputation(agent_id).await;
+    assert_eq!(view.total_jobs, 1, "Validated job's rating was not recorded");
+    println!("✅ Validated rated-job fed reputation: average {}", view.average);
+}
+
+#[tokio::test]
+async fn test_validation_score_bounds_cs() {
+    let RegisteredAgentSetup {
+        mut interactor,
+        owner: owner_wallet,
+        identity,
+        agent_id,
+        ..
+    } = setup_registered_agent("bounds-bot").await;
+
+    let validation = ValidationRegistryInteractor::deploy(
+        &mut interactor,
+        owner_wallet.clone(),
+        &identity.contract_address,
+    )
+    .await;
+    let validator = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let validator = register_wallet(&mut interactor, validator);
+    fund_default(&address_to_bech32(&validator)).await;
+    let hash = validation
+        .request_validation(&mut interactor, "bounds-job", agent_id, &validator)
+        .await
+        .expect("Validation request should be accepted");
+
+    // 1. Both ends of the range are valid; responses may be repeated
+    for score in [0, MAX_VALIDATION_SCORE] {
+        validation
+            .validation_response(&mut interactor, &validator, &hash, score, "bounds")
+            .await
+            .unwrap_or_else(|e| panic!("Score {} should be accepted: {}", score, e));
+        println!("✅ Score {} accepted", score);
+    }
+
+    // 2. Anything above the maximum reverts
+    for score in [MAX_VALIDATION_SCORE + 1, u8::MAX] {
+        let err = validation
+            .validation_response(&mut interactor, &validator, &hash, score, "bounds")
+            .await
+            .expect_err("Out-of-range score should revert");
+        let message = err
+            .revert_message()
+            .unwrap_or_else(|| panic!("Expected a contract revert, got {:?}", err));
+        println!("✅ Score {} rejected: {}", score, message);
+    }
+
+    generate_blocks_on_simulator(3).await;
+    let status = validation
+        .get_validation_status(&hash)
+        .await
+        .expect("Validation request missing");
+    assert_eq!(
+        status.response, MAX_VALIDATION_SCORE,
+        "Only in-range responses should be recorded"
+    );
+    assert_eq!(status.agent_nonce, agent_id);
+    assert_eq!(validation.get_validation_count(agent_id).await, 1);
+}
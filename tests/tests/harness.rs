@@ -0,0 +1,958 @@
+//! Harness Self-Tests
+//!
+//! Exercises the shared helpers in `common` that don't need a running
+//! chain simulator or backend: artifact checks, decoders, validators.
+
+mod common;
+use common::*;
+use std::time::Duration;
+
+#[test]
+fn test_check_wasm_size_rejects_oversized_artifact() {
+    let dir = scratch_dir("wasm-size");
+    let oversized = dir.join("oversized.wasm");
+    std::fs::write(&oversized, vec![0u8; 1025]).unwrap();
+    let path = oversized.to_str().unwrap();
+
+    let err = check_wasm_size_with_limit(path, 1024).expect_err("Oversized WASM should fail");
+    assert_eq!(
+        err,
+        HarnessError::WasmTooLarge {
+            path: path.to_string(),
+            size: 1025,
+            limit: 1024,
+        }
+    );
+    println!("✅ Oversized fixture rejected: {}", err);
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_check_wasm_size_accepts_normal_artifact() {
+    let dir = scratch_dir("wasm-size");
+    let small = dir.join("small.wasm");
+    std::fs::write(&small, vec![0u8; 512]).unwrap();
+
+    let size = check_wasm_size_with_limit(small.to_str().unwrap(), 1024).unwrap();
+    assert_eq!(size, 512);
+
+    // The real artifact must fit the protocol ceiling when setup.sh has been run
+    if let Ok(path) = resolve_artifact(IDENTITY_WASM) {
+        let size = check_wasm_size(&path).expect("Identity WASM exceeds size limit");
+        println!("✅ {} is {} bytes", path.display(), size);
+    }
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_sse_stream_decodes_tokens_until_done() {
+    let body = concat!(
+        "data: {\"token\": \"Multi\", \"done\": false}\n\n",
+        "data: {\"token\": \"versX\", \"done\": false}\n\n",
+        "data: {\"type\": \"text\", \"content\": \" DeFi\"}\n\n",
+        "data: {\"token\": null, \"done\": true}\n\n",
+        "data: {\"token\": \"ignored\", \"done\": false}\n\n",
+    );
+
+    let chunks = parse_sse_stream(body);
+    assert_eq!(chunks.len(), 4, "Parsing should stop at done: true");
+    assert!(chunks.last().unwrap().done);
+
+    let tokens = collect_tokens(&chunks);
+    assert!(!tokens.is_empty());
+    assert_eq!(tokens, "MultiversX DeFi");
+}
+
+#[test]
+fn test_sse_parser_handles_split_frames() {
+    let mut parser = SseParser::default();
+    assert!(parser.push(b"data: {\"token\": \"a\"").is_empty());
+    let events = parser.push(b", \"done\": false}\r\n\r\ndata: {\"done\": true}\n\n");
+    assert_eq!(events.len(), 2);
+
+    let first = StreamChunk::from_data(&events[0]).unwrap();
+    assert_eq!(first.token.as_deref(), Some("a"));
+    assert!(StreamChunk::from_data(&events[1]).unwrap().done);
+}
+
+#[test]
+fn test_sse_parser_keeps_utf8_split_across_chunks() {
+    // "é" is 0xc3 0xa9; the network splits it between two reads
+    let frame = "data: {\"token\": \"café\", \"done\": false}\n\n".as_bytes();
+    let split = frame.iter().position(|b| *b == 0xc3).unwrap() + 1;
+    let mut parser = SseParser::default();
+    assert!(parser.push(&frame[..split]).is_empty());
+    let events = parser.push(&frame[split..]);
+    assert_eq!(events.len(), 1);
+    let chunk = StreamChunk::from_data(&events[0]).unwrap();
+    assert_eq!(chunk.token.as_deref(), Some("café"));
+}
+
+#[tokio::test]
+async fn test_stream_chat_times_out_on_stalled_stream() {
+    let server = MockServer::start(vec![MockResponse::new(200)
+        .header("Content-Type", "text/event-stream")
+        .body("data: {\"token\": \"Multi\", \"done\": false}\n\n")
+        .body("data: {\"token\": \"versX\", \"done\": false}\n\n")
+        .chunk(
+            Duration::from_secs(5),
+            "data: {\"token\": null, \"done\": true}\n\n",
+        )])
+    .await;
+
+    let result =
+        stream_chat_at(&server.url, "session", "hello", Duration::from_millis(500)).await;
+    match result {
+        Err(StreamError::Timeout { waited_ms, partial }) => {
+            assert_eq!(waited_ms, 500);
+            assert_eq!(partial.len(), 2, "Chunks read before the stall are returned");
+            assert_eq!(collect_tokens(&partial), "MultiversX");
+        }
+        other => panic!("Expected a read timeout, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_sse_heartbeats_are_counted_not_collected() {
+    let body = concat!(
+        ": heartbeat\n\n",
+        "data: {\"token\": \"Multi\", \"done\": false}\n\n",
+        ": heartbeat\n\n",
+        ": heartbeat\n",
+        "data: {\"token\": \"versX\", \"done\": false}\n\n",
+        "data: {\"token\": null, \"done\": true}\n\n",
+    );
+    let server = MockServer::start(vec![MockResponse::new(200)
+        .header("Content-Type", "text/event-stream")
+        .body(body)])
+    .await;
+
+    let stats = run_research_session_at(&server.url, "session", &["hello"], Duration::from_secs(5))
+        .await
+        .expect("Mock stream should parse");
+    assert_eq!(stats.len(), 1);
+    assert_eq!(stats[0].heartbeats, 3);
+    assert_eq!(stats[0].chunks, 3, "Heartbeats are not chunks");
+    assert_eq!(stats[0].chars, "MultiversX".len());
+
+    let chunks = parse_sse_stream(body);
+    assert_eq!(collect_tokens(&chunks), "MultiversX");
+}
+
+#[test]
+fn test_assert_secure_uri() {
+    assert!(assert_secure_uri("https://research.openclaw.io").is_ok());
+    assert!(assert_secure_uri("HTTPS://research.openclaw.io").is_ok());
+    for uri in ["http://research.openclaw.io", "research.openclaw.io", "https://", ""] {
+        assert_eq!(
+            assert_secure_uri(uri),
+            Err(HarnessError::InsecureUri(uri.to_string())),
+            "{:?} should be rejected",
+            uri
+        );
+    }
+}
+
+#[test]
+fn test_parse_token_amount() {
+    let units = |amount: &str| parse_token_amount(amount, USDC_DECIMALS).map(|v| v.to_string());
+    assert_eq!(units("0.50").as_deref(), Some("500000"));
+    assert_eq!(units("2").as_deref(), Some("2000000"));
+    assert_eq!(units(".5").as_deref(), Some("500000"));
+    assert_eq!(units("1.000001").as_deref(), Some("1000001"));
+    assert_eq!(units("0.0000001"), None, "More precision than the token has");
+    assert_eq!(units("1,50"), None);
+    assert_eq!(units("-1"), None);
+    assert_eq!(units(""), None);
+}
+
+#[test]
+fn test_payment_required_validates_amount() {
+    let body = |amount: &str| {
+        serde_json::json!({
+            "sessionId": "s-1",
+            "payment": { "amount": amount, "token": "USDC-c76f1f", "receiver": "erd1agent" }
+        })
+    };
+
+    let payment = PaymentRequired::from_json(&body("1000000")).expect("Numeric amount decodes");
+    assert_eq!(payment.amount, "1000000");
+    assert_eq!(payment.recipient, "erd1agent");
+    assert!(PaymentRequired::from_json(&body("0.50")).is_ok());
+
+    let err = PaymentRequired::from_json(&body("abc")).expect_err("\"abc\" is not an amount");
+    assert!(
+        matches!(&err, HarnessError::Schema { field, .. } if field == "payment.amount"),
+        "got {:?}",
+        err
+    );
+    assert!(err.to_string().contains("\"abc\""), "message should quote the value: {}", err);
+}
+
+#[tokio::test]
+async fn test_download_resumes_after_disconnect() {
+    let report = b"# Research Report\n\nMultiversX DeFi overview.\n".to_vec();
+    let (head, tail) = report.split_at(12);
+    let server = MockServer::start(vec![
+        // Promises the whole report, then drops the connection partway
+        MockResponse::new(200)
+            .header("Content-Length", &report.len().to_string())
+            .body(head),
+        MockResponse::new(206)
+            .header(
+                "Content-Range",
+                &format!("bytes {}-{}/{}", head.len(), report.len() - 1, report.len()),
+            )
+            .header("Content-Length", &tail.len().to_string())
+            .body(tail),
+    ])
+    .await;
+
+    let dir = scratch_dir("resumable-download");
+    let dest = dir.join("report.md");
+    let written = download_resumable(&format!("{}/report.md", server.url), &dest)
+        .await
+        .expect("Download should resume and complete");
+
+    assert_eq!(written, report.len() as u64);
+    assert_eq!(std::fs::read(&dest).unwrap(), report);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(
+        requests[1].to_lowercase().contains("range: bytes=12-"),
+        "Resume should request the missing tail, got:\n{}",
+        requests[1]
+    );
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_event_order() {
+    let tx = serde_json::json!({
+        "logs": { "events": [{ "identifier": "ESDTNFTCreate" }] },
+        "smartContractResults": [
+            { "logs": { "events": [{ "identifier": "agentRegistered" }] } },
+            { "data": "@6f6b" },
+            { "logs": { "events": [{ "identifier": "completedTxEvent" }] } },
+        ],
+    });
+    let events = tx_event_identifiers(&tx);
+    assert_eq!(events, ["ESDTNFTCreate", "agentRegistered", "completedTxEvent"]);
+
+    assert!(check_event_order(&events, &["ESDTNFTCreate", "agentRegistered"]).is_ok());
+    assert!(check_event_order(&events, &["ESDTNFTCreate", "completedTxEvent"]).is_ok());
+    let err = check_event_order(&events, &["agentRegistered", "ESDTNFTCreate"]).unwrap_err();
+    assert!(err.contains("ESDTNFTCreate is out of order"), "{}", err);
+    let err = check_event_order(&events, &["ESDTNFTCreate", "agentUpdated"]).unwrap_err();
+    assert!(err.contains("agentUpdated is missing"), "{}", err);
+}
+
+#[test]
+fn test_event_topics_are_decoded() {
+    // "refund", then 0x0de0b6b3a7640000 (1 EGLD), then a topic that isn't base64
+    let event = serde_json::json!({
+        "identifier": "refund",
+        "topics": ["cmVmdW5k", "DeC2s6dkAAA=", "not base64!"],
+    });
+    let topics = event_topics(&event);
+    assert_eq!(topics[0], b"refund");
+    assert_eq!(num_bigint::BigUint::from_bytes_be(&topics[1]).to_string(), egld(1));
+    assert!(topics[2].is_empty());
+    assert!(event_topics(&serde_json::json!({})).is_empty());
+}
+
+#[tokio::test]
+async fn test_html_error_page_is_reported_not_parsed() {
+    let page = format!(
+        "<html><head><title>502 Bad Gateway</title></head><body>{}</body></html>",
+        "nginx ".repeat(100)
+    );
+    let server = MockServer::start(vec![MockResponse::new(502)
+        .header("Content-Type", "text/html")
+        .body(page.clone())])
+    .await;
+
+    let err = get_json(&format!("{}/network/config", server.url))
+        .await
+        .expect_err("HTML must not decode as JSON");
+    match &err {
+        HarnessError::UnexpectedResponse {
+            status,
+            content_type,
+            body_preview,
+            ..
+        } => {
+            assert_eq!(*status, 502);
+            assert_eq!(content_type, "text/html");
+            assert_eq!(body_preview.len(), 200);
+            assert!(page.starts_with(body_preview.as_str()));
+        }
+        other => panic!("Expected UnexpectedResponse, got {:?}", other),
+    }
+    assert!(err.to_string().contains("502 Bad Gateway"));
+}
+
+#[test]
+fn test_nft_identifier_uses_even_length_hex_nonce() {
+    assert_eq!(nft_identifier("OCAGENT-a1b2c3", 1), "OCAGENT-a1b2c3-01");
+    assert_eq!(nft_identifier("OCAGENT-a1b2c3", 0x1f), "OCAGENT-a1b2c3-1f");
+    assert_eq!(nft_identifier("OCAGENT-a1b2c3", 256), "OCAGENT-a1b2c3-0100");
+}
+
+#[tokio::test]
+async fn test_poll_until_returns_first_accepted_value() {
+    let mut calls = 0u32;
+    let value = poll_until(
+        "counter",
+        || {
+            calls += 1;
+            let n = calls;
+            async move { Ok(n) }
+        },
+        |n| *n >= 3,
+        Duration::from_millis(10),
+        Duration::from_secs(1),
+    )
+    .await
+    .expect("counter reaches 3");
+
+    assert_eq!(value, 3);
+    assert_eq!(calls, 3, "poll_until must stop as soon as the predicate holds");
+}
+
+#[tokio::test]
+async fn test_poll_until_times_out() {
+    let err = poll_until(
+        "never",
+        || async { Ok(false) },
+        |done| *done,
+        Duration::from_millis(10),
+        Duration::from_millis(50),
+    )
+    .await
+    .expect_err("predicate never holds");
+    assert!(
+        matches!(&err, HarnessError::Timeout { what, .. } if what == "never"),
+        "got {:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_readiness_polling_tolerates_latency() {
+    let server = MockServer::start(vec![MockResponse::new(200)
+        .header("Content-Type", "application/json")
+        .body(r#"{"status":"ok"}"#)])
+    .await;
+    let client = SlowClient::new(Duration::from_secs(2));
+
+    let started = std::time::Instant::now();
+    wait_for_ready_at(&client, &server.url, Duration::from_secs(10))
+        .await
+        .expect("Slow but healthy server should become ready");
+    assert!(started.elapsed() >= Duration::from_secs(2), "Latency was not injected");
+    assert!(server.requests()[0].starts_with("GET /api/health"));
+}
+
+#[test]
+fn test_contract_address_marker() {
+    // 8 zero bytes, then the WASM VM type 0x0500
+    let mut bytes = [0u8; 32];
+    bytes[8] = 0x05;
+    bytes[31] = 0x2a;
+    let contract = multiversx_sc::types::Address::from(bytes);
+    assert!(assert_is_contract_address(&contract).is_ok());
+
+    let wallet = Wallet::from_private_key(&generate_random_private_key())
+        .unwrap()
+        .to_address();
+    assert_eq!(
+        assert_is_contract_address(&wallet),
+        Err(HarnessError::NotAContract(address_to_bech32(&wallet)))
+    );
+}
+
+#[test]
+fn test_computed_contract_address_shape() {
+    let deployer = Wallet::from_private_key(&generate_random_private_key())
+        .unwrap()
+        .to_address();
+    let first = compute_contract_address(&deployer, 0);
+
+    assert!(assert_is_contract_address(&first).is_ok());
+    assert_eq!(first.as_bytes()[8..10], [0x05, 0x00], "WASM VM type expected");
+    assert_eq!(first.as_bytes()[30..], deployer.as_bytes()[30..], "Shard suffix lost");
+    assert_eq!(first, compute_contract_address(&deployer, 0));
+    assert_ne!(first, compute_contract_address(&deployer, 1));
+}
+
+fn sample_agent() -> AgentDetails {
+    AgentDetails {
+        name: "diff-bot".to_string(),
+        public_key: [7u8; 32],
+    }
+}
+
+#[test]
+fn test_agent_diff_names_changed_fields() {
+    let before = sample_agent();
+    assert!(agent_diff(&before, &before.clone()).is_empty());
+    assert_agent_eq(&before, &before.clone());
+
+    let after = AgentDetails {
+        name: "renamed-bot".to_string(),
+        ..before.clone()
+    };
+    assert_eq!(
+        agent_diff(&before, &after),
+        vec![r#"name: "diff-bot" -> "renamed-bot""#.to_string()]
+    );
+}
+
+#[test]
+#[should_panic(expected = "name: \"diff-bot\" -> \"renamed-bot\"")]
+fn test_assert_agent_eq_reports_name_change() {
+    let before = sample_agent();
+    let after = AgentDetails {
+        name: "renamed-bot".to_string(),
+        ..before.clone()
+    };
+    assert_agent_eq(&before, &after);
+}
+
+#[test]
+fn test_agent_list_entries_decode() {
+    let owner = [9u8; 32];
+    let mut page = Vec::new();
+    for (nonce, name) in [(1u64, "first-bot"), (2, "second-bot")] {
+        page.extend_from_slice(&nonce.to_be_bytes());
+        page.extend_from_slice(&owner);
+        encode_nested_buffer(&mut page, name.as_bytes());
+        encode_nested_buffer(&mut page, &[nonce as u8; 32]);
+    }
+
+    let entries = AgentListEntry::decode_list(&page).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1].nonce, 2);
+    assert_eq!(entries[1].owner, multiversx_sc::types::Address::from_slice(&owner));
+    assert_eq!(entries[1].details.name, "second-bot");
+    assert_eq!(entries[1].details.public_key, [2u8; 32]);
+    assert!(AgentListEntry::decode_list(&[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_registered_agent_decodes_event() {
+    let owner = [3u8; 32];
+    let topics = vec![b"agentRegistered".to_vec(), owner.to_vec(), vec![0x2a]];
+    let mut data = Vec::new();
+    encode_nested_buffer(&mut data, b"event-bot");
+    encode_nested_buffer(&mut data, b"https://research.openclaw.io");
+
+    let agent = RegisteredAgent::decode_event(&topics, &data).unwrap();
+    assert_eq!(agent.nonce, 42);
+    assert_eq!(agent.owner, multiversx_sc::types::Address::from_slice(&owner));
+    assert_eq!(agent.name, "event-bot");
+    assert_eq!(agent.uri, "https://research.openclaw.io");
+    assert!(RegisteredAgent::decode_event(&topics[..1], &data).is_err());
+
+    let twin = RegisteredAgent {
+        nonce: 43,
+        name: "twin-bot".to_string(),
+        ..agent.clone()
+    };
+    assert_eq!(
+        find_duplicate_uris(&[agent.clone(), twin]),
+        vec![(agent.uri.clone(), vec!["event-bot".to_string(), "twin-bot".to_string()])]
+    );
+    assert_unique_uris(&[agent]);
+}
+
+#[test]
+fn test_scenario_script_parses() {
+    let script = ScenarioScript::load("scenarios/register_and_pay.json").expect("Valid scenario");
+    assert_eq!(script.steps.first(), Some(&ScenarioStep::Deploy));
+    assert!(script.steps.contains(&ScenarioStep::Query {
+        name: "unknown-bot".to_string(),
+        uri: None,
+        exists: false,
+    }));
+    assert_eq!(script.expect.agent_count, Some(1));
+
+    let dir = scratch_dir("scenario");
+    let path = dir.join("bad.json");
+    std::fs::write(&path, r#"{ "steps": [{ "step": "teleport" }] }"#).unwrap();
+    let err = ScenarioScript::load(path.to_str().unwrap()).expect_err("Unknown step");
+    assert!(err.to_string().contains("teleport"), "got {}", err);
+}
+
+#[test]
+fn test_resolve_artifact_prefers_env_dir() {
+    let dir = scratch_dir("artifacts-env");
+    std::fs::write(dir.join("dummy-registry.wasm"), [0u8; 16]).unwrap();
+
+    std::env::set_var(ARTIFACTS_DIR_ENV, &dir);
+    let resolved = resolve_artifact("dummy-registry.wasm");
+    let missing = resolve_artifact("absent-registry.wasm");
+    std::env::remove_var(ARTIFACTS_DIR_ENV);
+
+    assert_eq!(resolved.unwrap(), dir.join("dummy-registry.wasm"));
+    match missing {
+        Err(HarnessError::MissingArtifacts { dir: searched, missing }) => {
+            assert_eq!(missing, vec!["absent-registry.wasm".to_string()]);
+            assert!(searched.contains(&dir.display().to_string()), "searched {}", searched);
+            assert!(searched.contains("artifacts"));
+        }
+        other => panic!("Expected MissingArtifacts, got {:?}", other),
+    }
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[tokio::test]
+async fn test_research_session_stops_when_payment_expires() {
+    let server = MockServer::start(vec![
+        MockResponse::new(200)
+            .header("Content-Type", "text/event-stream")
+            .body("data: {\"token\": \"EGLD\", \"done\": false}\n\n")
+            .body("data: {\"token\": null, \"done\": true}\n\n"),
+        MockResponse::new(402)
+            .header("Content-Type", "application/json")
+            .body(r#"{"sessionId":"fresh","payment":{}}"#),
+    ])
+    .await;
+
+    let queries = ["first", "second", "third"];
+    let stats = run_research_session_at(&server.url, "session", &queries, Duration::from_secs(5))
+        .await
+        .expect("Expiry should end the session, not fail it");
+
+    assert_eq!(stats.len(), 1, "Only the query before expiry completes");
+    assert_eq!(stats[0].query, "first");
+    assert_eq!(stats[0].chunks, 2);
+    assert_eq!(stats[0].chars, 4);
+}
+
+#[test]
+fn test_check_running_average() {
+    let view = |average, total_jobs| ReputationView { average, total_jobs };
+    assert!(check_running_average(&view(0, 0), &[]).is_ok());
+    // 5 + 4 = 9 over 2 jobs: 4 (truncated) and 5 (rounded) are both fine
+    assert!(check_running_average(&view(4, 2), &[5, 4]).is_ok());
+    assert!(check_running_average(&view(5, 2), &[5, 4]).is_ok());
+    assert!(check_running_average(&view(3, 2), &[5, 4]).is_err());
+    // A dropped rating shows up in the count
+    assert!(check_running_average(&view(5, 1), &[5, 4]).is_err());
+}
+
+#[tokio::test]
+async fn test_get_json_waits_out_rate_limit() {
+    let server = MockServer::start(vec![
+        MockResponse::new(429).header("Retry-After", "1"),
+        MockResponse::new(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"data":{"status":"ok"}}"#),
+    ])
+    .await;
+
+    let started = std::time::Instant::now();
+    let body = get_json(&format!("{}/network/status/0", server.url))
+        .await
+        .expect("Helper should recover after the 429");
+    assert_eq!(body["data"]["status"], "ok");
+    assert!(started.elapsed() >= Duration::from_secs(1), "Retry-After was not honored");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn test_persistent_rate_limit_is_typed() {
+    let server = MockServer::start(vec![MockResponse::new(429).header("Retry-After", "0")]).await;
+
+    let err = get_json(&server.url).await.expect_err("Never stops rate limiting");
+    assert_eq!(
+        err,
+        HarnessError::RateLimited {
+            retry_after: Some(Duration::ZERO)
+        }
+    );
+    assert_eq!(server.requests().len() as u32, MAX_RATE_LIMIT_RETRIES + 1);
+}
+
+#[test]
+fn test_parse_version_orders_releases() {
+    assert_eq!(parse_version("v1.7.13"), Some((1, 7, 13)));
+    assert_eq!(parse_version("1.6"), Some((1, 6, 0)));
+    assert_eq!(parse_version("v1.8.0-rc1"), Some((1, 8, 0)));
+    assert_eq!(parse_version("latest"), None);
+    assert!(parse_version("v1.10.0") > parse_version("v1.9.5"));
+}
+
+#[test]
+fn test_session_budget_parsing_and_overruns() {
+    let budget = SessionTiming::parse_budget("1000, 500, 3000, 60000").unwrap();
+    assert_eq!(budget.first_token_ms, 3000);
+    assert_eq!(SessionTiming::parse_budget("1000,500"), None);
+    assert_eq!(SessionTiming::parse_budget("1000,500,fast,60000"), None);
+
+    let timing = SessionTiming {
+        chat_402_ms: 800,
+        confirm_ms: 700,
+        first_token_ms: 2500,
+        report_ms: 61000,
+    };
+    assert_eq!(
+        timing.over_budget(&budget),
+        vec!["confirm: 700ms > 500ms", "report: 61000ms > 60000ms"]
+    );
+}
+
+#[test]
+fn test_wallet_manifest_resolves_pem_paths() {
+    let dir = scratch_dir("manifest");
+    let key = generate_random_private_key();
+    write_pem(dir.join("owner.pem"), &key).unwrap();
+    std::fs::write(dir.join("wallets.json"), r#"{ "owner": "owner.pem" }"#).unwrap();
+
+    let wallets = load_wallets_from_manifest(dir.join("wallets.json")).unwrap();
+    assert_eq!(wallets.len(), 1);
+    assert_eq!(wallets[0].0, "owner");
+    assert_eq!(
+        wallets[0].1.to_address(),
+        Wallet::from_private_key(&key).unwrap().to_address()
+    );
+
+    std::fs::write(dir.join("bad.json"), r#"{ "owner": 42 }"#).unwrap();
+    assert!(matches!(
+        load_wallets_from_manifest(dir.join("bad.json")),
+        Err(HarnessError::Schema { .. })
+    ));
+}
+
+#[test]
+fn test_decode_managed_string() {
+    assert_eq!(decode_managed_string(b"research-bot").unwrap(), "research-bot");
+    assert_eq!(decode_managed_string("🤖-bot".as_bytes()).unwrap(), "🤖-bot");
+    assert_eq!(decode_managed_string(&[]).unwrap(), "");
+
+    let err = decode_managed_string(&[0x62, 0x6f, 0x74, 0xff, 0x00]).unwrap_err();
+    assert_eq!(
+        err,
+        HarnessError::InvalidUtf8 {
+            valid_up_to: 3,
+            hex: "626f74ff00".to_string(),
+        }
+    );
+    assert!(err.to_string().contains("0x626f74ff00"), "{}", err);
+
+    // An agent whose name is binary is reported, not decoded lossily
+    let mut agent = Vec::new();
+    encode_nested_buffer(&mut agent, &[0xc3, 0x28]);
+    encode_nested_buffer(&mut agent, &[0u8; 32]);
+    assert!(matches!(
+        AgentDetails::try_decode(&agent),
+        Err(HarnessError::InvalidUtf8 { valid_up_to: 0, .. })
+    ));
+}
+
+#[test]
+fn test_agent_details_rejects_bad_key_and_truncation() {
+    // A public key that isn't 32 bytes is a schema error, not a panic
+    let mut agent = Vec::new();
+    encode_nested_buffer(&mut agent, b"bot");
+    encode_nested_buffer(&mut agent, &[0u8; 31]);
+    let err = AgentDetails::try_decode(&agent).unwrap_err();
+    assert!(
+        matches!(&err, HarnessError::Schema { field, .. } if field == "public_key"),
+        "{:?}",
+        err
+    );
+
+    // So is a buffer whose length prefix runs past the end of the data
+    let truncated = &agent[..agent.len() - 10];
+    assert!(matches!(
+        AgentDetails::try_decode(truncated),
+        Err(HarnessError::Schema { .. })
+    ));
+    assert!(matches!(
+        AgentDetails::try_decode(&[0, 0]),
+        Err(HarnessError::Schema { .. })
+    ));
+}
+
+#[test]
+fn test_register_agent_args_encode_metadata() {
+    let args = IdentityRegistryInteractor::register_agent_args_with_metadata(
+        "bot",
+        "https://research.openclaw.io",
+        &[0u8; 32],
+        &[("k", b"vv".as_slice())],
+        &[],
+    );
+    assert_eq!(args.len(), 6);
+    assert_eq!(args[3], 1u32.to_be_bytes());
+    assert_eq!(args[4], [0, 0, 0, 1, b'k', 0, 0, 0, 2, b'v', b'v']);
+    assert_eq!(args[5], 0u32.to_be_bytes());
+
+    let plain = IdentityRegistryInteractor::register_agent_args("bot", "u", &[0u8; 32], &[]);
+    assert_eq!(plain.len(), 5);
+    assert_eq!(plain[3], 0u32.to_be_bytes(), "No metadata by default");
+}
+
+#[test]
+fn test_update_agent_args_omit_unset_lists() {
+    let plain = IdentityRegistryInteractor::update_agent_args("bot", "u", &[0u8; 32], None, None);
+    assert_eq!(plain.len(), 3, "Name, uri and public key only");
+
+    let metadata = [("k", b"vv".as_slice())];
+    let with_metadata = IdentityRegistryInteractor::update_agent_args(
+        "bot",
+        "u",
+        &[0u8; 32],
+        Some(&metadata),
+        None,
+    );
+    assert_eq!(with_metadata.len(), 5);
+    assert_eq!(with_metadata[3], 1u32.to_be_bytes());
+    assert_eq!(with_metadata[4], [0, 0, 0, 1, b'k', 0, 0, 0, 2, b'v', b'v']);
+
+    let services_only =
+        IdentityRegistryInteractor::update_agent_args("bot", "u", &[0u8; 32], None, Some(&[]));
+    assert_eq!(services_only.len(), 5);
+    assert_eq!(services_only[3], 0u32.to_be_bytes(), "Empty metadata precedes services");
+    assert_eq!(services_only[4], 0u32.to_be_bytes());
+}
+
+#[test]
+fn test_service_config_encodes_and_payment_decodes() {
+    let config = ServiceConfigInput {
+        service_id: 1,
+        price: 500_000u64.into(),
+        token: "USDC-350c4e".to_string(),
+        nonce: 0,
+    };
+    let mut expected = vec![0, 0, 0, 1, 0, 0, 0, 3, 0x07, 0xa1, 0x20, 0, 0, 0, 11];
+    expected.extend_from_slice(b"USDC-350c4e");
+    expected.extend_from_slice(&0u64.to_be_bytes());
+    assert_eq!(config.encode_nested(), expected);
+
+    let args = IdentityRegistryInteractor::register_agent_args("bot", "u", &[0u8; 32], &[config]);
+    assert_eq!(args[4], 1u32.to_be_bytes(), "One service");
+    assert_eq!(args[5], expected);
+
+    let mut payment = Vec::new();
+    encode_nested_buffer(&mut payment, b"USDC-350c4e");
+    payment.extend_from_slice(&0u64.to_be_bytes());
+    encode_nested_buffer(&mut payment, &[0x07, 0xa1, 0x20]);
+    assert_eq!(
+        TokenPayment::decode(&payment).unwrap(),
+        TokenPayment {
+            token_identifier: "USDC-350c4e".to_string(),
+            token_nonce: 0,
+            amount: 500_000u64.into(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_cors_preflight_checks_allowed_origin() {
+    let preflight = |allow_origin: &str| {
+        MockResponse::new(204)
+            .header("Access-Control-Allow-Origin", allow_origin)
+            .header("Access-Control-Allow-Methods", "GET,POST,DELETE")
+            .header("Access-Control-Allow-Headers", "Content-Type,Authorization")
+    };
+    let server = MockServer::start(vec![
+        preflight("*"),
+        preflight("http://localhost:3000"),
+        preflight("https://elsewhere.example"),
+        MockResponse::new(204),
+    ])
+    .await;
+
+    let origin = "http://localhost:3000";
+    assert!(cors_allowed_at(&server.url, "/api/chat", origin).await.is_ok());
+    assert!(cors_allowed_at(&server.url, "/api/chat", origin).await.is_ok());
+    for _ in 0..2 {
+        match cors_allowed_at(&server.url, "/api/chat", origin).await {
+            Err(HarnessError::CorsRejected { path, .. }) => assert_eq!(path, "/api/chat"),
+            other => panic!("Expected a CORS rejection, got {:?}", other),
+        }
+    }
+    assert!(server.requests()[0].starts_with("OPTIONS /api/chat"));
+}
+
+#[test]
+fn test_decode_reputations_batch() {
+    let values = vec![vec![4], vec![2], vec![], vec![]];
+    let views = decode_reputations_batch(&values, 2).unwrap();
+    assert_eq!(views[0], ReputationView { average: 4, total_jobs: 2 });
+    assert_eq!(views[1], ReputationView::default(), "Empty values decode as zero");
+
+    assert!(matches!(
+        decode_reputations_batch(&values[..3], 2),
+        Err(HarnessError::Schema { .. })
+    ));
+}
+
+#[test]
+fn test_session_id_randomness_accepts_uuids() {
+    let ids: Vec<String> = (0..100)
+        .map(|_| {
+            let h = hex::encode(rand::random::<[u8; 16]>());
+            format!("{}-{}-{}-{}-{}", &h[..8], &h[8..12], &h[12..16], &h[16..20], &h[20..])
+        })
+        .collect();
+    assert_session_id_random(&ids);
+}
+
+#[test]
+#[should_panic(expected = "ids look sequential")]
+fn test_session_id_randomness_rejects_counters() {
+    let ids: Vec<String> = (0..100).map(|i| format!("session-{:06}", i)).collect();
+    assert_session_id_random(&ids);
+}
+
+#[test]
+#[should_panic(expected = "bits")]
+fn test_session_id_randomness_rejects_short_ids() {
+    let ids: Vec<String> = (0..100).map(|_| hex::encode(rand::random::<[u8; 4]>())).collect();
+    assert_session_id_random(&ids);
+}
+
+#[test]
+fn test_find_callback_result() {
+    let tx = serde_json::json!({
+        "smartContractResults": [
+            { "callType": 1, "receiver": "erd1system", "data": "issue@..." },
+            { "callType": 2, "receiver": "erd1registry", "data": "@00@4f43" },
+        ]
+    });
+    let callback = find_callback_result(&tx).expect("Callback result not found");
+    assert_eq!(callback["receiver"], "erd1registry");
+
+    let pending = serde_json::json!({ "smartContractResults": [{ "callType": 1 }] });
+    assert!(find_callback_result(&pending).is_none());
+    assert!(find_callback_result(&serde_json::json!({})).is_none());
+}
+
+#[test]
+fn test_blocks_per_sec() {
+    let rate = BlocksPerSec::new(20, Duration::from_secs(4));
+    assert_eq!(rate, BlocksPerSec(5.0));
+    assert_eq!(rate.to_string(), "5.0 blocks/s");
+    assert!(BlocksPerSec::new(1, Duration::ZERO).0.is_finite());
+}
+
+#[tokio::test]
+async fn test_tx_submission_retries_transient_gateway_error() {
+    let server = MockServer::start(vec![
+        MockResponse::new(502),
+        MockResponse::new(200)
+            .header("Content-Type", "application/json")
+            .body(r#"{"data":{"txHash":"abc123"},"error":"","code":"successful"}"#),
+    ])
+    .await;
+
+    let wallet = Wallet::from_private_key(&generate_random_private_key()).unwrap();
+    let sender = address_to_bech32(&wallet.to_address());
+    let unsigned = serde_json::json!({
+        "nonce": 0,
+        "value": "1",
+        "receiver": sender,
+        "sender": sender,
+        "gasPrice": 1_000_000_000u64,
+        "gasLimit": 50_000u64,
+        "chainID": "chain",
+        "version": 1,
+    });
+
+    let hash = sign_and_submit_at(&server.url, &wallet, unsigned)
+        .await
+        .expect("Submission should go through after the 502");
+    assert_eq!(hash, "abc123");
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn test_ensure_artifacts_names_missing_files() {
+    let dir = scratch_dir("artifacts-empty");
+
+    let err = ensure_artifacts_in(&dir).expect_err("Empty dir should fail the pre-check");
+    match &err {
+        HarnessError::MissingArtifacts { missing, .. } => {
+            assert_eq!(missing, &ARTIFACT_FILES.map(String::from).to_vec());
+        }
+        other => panic!("Expected MissingArtifacts, got {:?}", other),
+    }
+    let message = err.to_string();
+    assert!(message.contains("identity-registry.wasm"));
+    assert!(message.contains("setup.sh"));
+
+    // Only the files still absent are reported
+    std::fs::write(dir.join("identity-registry.wasm"), b"\0asm").unwrap();
+    match ensure_artifacts_in(&dir) {
+        Err(HarnessError::MissingArtifacts { missing, .. }) => {
+            assert_eq!(missing, vec!["validation-registry.wasm", "reputation-registry.wasm"]);
+        }
+        other => panic!("Expected two missing artifacts, got {:?}", other),
+    }
+
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+fn token(text: &str) -> StreamChunk {
+    StreamChunk {
+        token: Some(text.to_string()),
+        ..Default::default()
+    }
+}
+
+fn done() -> StreamChunk {
+    StreamChunk {
+        done: true,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_validate_stream_accepts_well_formed_stream() {
+    let chunks = vec![token("Multi"), token("versX"), done()];
+    assert_eq!(validate_stream(&chunks), Ok(()));
+}
+
+#[test]
+fn test_validate_stream_rejects_malformed_streams() {
+    // done arrives before the content has finished
+    let out_of_order = vec![token("Multi"), done(), token("versX")];
+    assert_eq!(
+        validate_stream(&out_of_order),
+        Err(StreamError::ChunksAfterDone {
+            done_index: 1,
+            total: 3
+        })
+    );
+
+    let truncated = vec![token("Multi"), token("versX")];
+    assert_eq!(validate_stream(&truncated), Err(StreamError::MissingDone));
+
+    let double_done = vec![token("Multi"), done(), done()];
+    assert_eq!(
+        validate_stream(&double_done),
+        Err(StreamError::MultipleDone { count: 2 })
+    );
+}
+
+#[test]
+fn test_usage_cost_counts_streamed_tokens() {
+    let chunks = vec![token("Multi"), token("versX"), token(" DeFi"), done()];
+    let price = num_bigint::BigUint::from(1_000u64);
+
+    assert_eq!(compute_usage_cost(&chunks, price.clone()), 3_000u64.into());
+    assert_eq!(compute_usage_cost(&[done()], price.clone()), 0u64.into());
+    assert_eq!(
+        settlement_amount(&chunks, &PricingModel::PerToken(price.clone())),
+        3_000u64.into()
+    );
+    assert_eq!(settlement_amount(&chunks, &PricingModel::Flat(price.clone())), price);
+}